@@ -0,0 +1,230 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::settlement::*;
+use crate::types::*;
+
+#[blueprint]
+#[events(
+    CrowdfundContributed,
+    CrowdfundTargetReached,
+    CrowdfundShareClaimed,
+    CrowdfundContributionRefunded,
+    CrowdfundOfferRecovered
+)]
+mod crowdfund_escrow {
+    // Sells the offered resources to many contributors at once. Contributions are collected until the target
+    // is hit, after which the instantiator withdraws them and every contributor claims their pro-rata share.
+    // If the deadline passes first, contributors are refunded and the instantiator recovers the offer.
+    struct CrowdfundEscrow {
+        offered_resources: IndexMap<ResourceAddress, Vault>,
+        // Offered amounts at instantiation, which the shares of the contributors are taken from
+        offered_amounts: IndexMap<ResourceAddress, Decimal>,
+        contribution_resource: ResourceAddress,
+        target_amount: Decimal,
+        deadline_epoch: u64,
+        contributions: Vault,
+        contributed_amount: Decimal,
+        contribution_badge: ResourceManager,
+        contribution_count: u64,
+        crowdfund_nft: ResourceAddress,
+        crowdfund_nft_id: NonFungibleLocalId,
+    }
+
+    impl CrowdfundEscrow {
+
+        pub fn instantiate_crowdfund_escrow(
+            offered_resources: Vec<Bucket>,
+            contribution_resource: ResourceAddress,
+            target_amount: Decimal,
+            deadline_epoch: u64
+        ) -> (Global<CrowdfundEscrow>, NonFungibleBucket) {
+            assert!(!offered_resources.is_empty(), "At least one offered resource is required");
+            assert!(
+                offered_resources.iter().all(|bucket| bucket.resource_address().is_fungible()),
+                "Offered resources have to be fungible to be shared"
+            );
+            assert!(contribution_resource.is_fungible(), "Contributions have to be made in a fungible resource");
+            assert!(target_amount > Decimal::ZERO, "Target amount must be positive");
+            assert!(deadline_epoch > Runtime::current_epoch().number(), "Deadline must be in the future");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(CrowdfundEscrow::blueprint_id());
+
+            // The crowdfund is managed with the same badge as an escrow, asking for the target amount
+            let badge = escrow_badge_builder(component_address)
+                .mint_initial_supply([(1u64.into(), EscrowBadge {
+                    requested_resources: vec![crate::ResourceSpecifier::Fungible {
+                        resource_address: contribution_resource,
+                        amount: target_amount,
+                    }],
+                    offered_resources: amounts_of(&offered_resources),
+                    created_epoch: Runtime::current_epoch().number(),
+                    expiry_epoch: Some(deadline_epoch),
+                    currency_prices: IndexMap::new(),
+                    escrow_component: component_address,
+                })]);
+            let contribution_badge = ticket_builder::<ContributionBadge>(component_address, "Scrypto 101 Crowdfund Contribution")
+                .create_with_no_initial_supply();
+
+            let component = Self {
+                offered_amounts: amounts_of(&offered_resources),
+                offered_resources: vaults_of(offered_resources),
+                contribution_resource,
+                target_amount,
+                deadline_epoch,
+                contributions: Vault::new(contribution_resource),
+                contributed_amount: Decimal::ZERO,
+                contribution_badge,
+                contribution_count: 0,
+                crowdfund_nft: badge.resource_address(),
+                crowdfund_nft_id: badge.non_fungible_local_id(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, badge)
+        }
+
+        // Takes a contribution up to what is missing from the target, returning the contribution badge
+        // and whatever is left of the payment
+        pub fn contribute(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            assert!(Runtime::current_epoch().number() < self.deadline_epoch, "Crowdfund has ended");
+            assert!(!self.is_target_reached(), "Crowdfund has already reached its target");
+            assert!(payment.resource_address() == self.contribution_resource, "Invalid contribution resource");
+
+            let amount = payment.amount().min(self.target_amount - self.contributed_amount);
+            assert!(amount > Decimal::ZERO, "Empty contribution");
+            self.contributions.put(payment.take(amount));
+            self.contributed_amount += amount;
+
+            self.contribution_count += 1;
+            let contribution_nft = self.contribution_badge.mint_non_fungible(
+                &NonFungibleLocalId::integer(self.contribution_count),
+                ContributionBadge {
+                    crowdfund_component: Runtime::global_address(),
+                    amount,
+                    contributed_epoch: Runtime::current_epoch().number(),
+                },
+            );
+            Runtime::emit_event(CrowdfundContributed {
+                crowdfund_nft_id: self.crowdfund_nft_id.clone(),
+                contribution_nft_id: contribution_nft.as_non_fungible().non_fungible_local_id(),
+                amount,
+            });
+            if self.is_target_reached() {
+                Runtime::emit_event(CrowdfundTargetReached {
+                    crowdfund_nft_id: self.crowdfund_nft_id.clone(),
+                    target_amount: self.target_amount,
+                });
+            }
+
+            (contribution_nft, payment)
+        }
+
+        // Method allows the instantiator to withdraw the contributions once the target has been hit
+        pub fn withdraw_contributions(&mut self, crowdfund_nft: NonFungibleProof) -> Bucket {
+            self.verify_crowdfund_badge(crowdfund_nft);
+            assert!(self.is_target_reached(), "Crowdfund has not reached its target");
+
+            self.contributions.take_all()
+        }
+
+        // Burns a contribution badge for its share of every offered resource, once the target has been hit
+        pub fn claim_share(&mut self, contribution_nft: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(self.is_target_reached(), "Crowdfund has not reached its target");
+            let (contribution_nft_id, amount) = self.burn_contribution_badge(contribution_nft);
+
+            let claimed: Vec<Bucket> = self.offered_resources
+                .iter_mut()
+                .map(|(resource_address, vault)| {
+                    let share = proportional_amount(self.offered_amounts[resource_address], amount, self.target_amount);
+                    // The last claims may find a little less left over after rounding
+                    vault.take_advanced(share.min(vault.amount()), WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .collect();
+            Runtime::emit_event(CrowdfundShareClaimed {
+                crowdfund_nft_id: self.crowdfund_nft_id.clone(),
+                contribution_nft_id,
+                claimed: amounts_of(&claimed),
+            });
+
+            claimed
+        }
+
+        // Burns a contribution badge for the exact deposit it stands for, once the crowdfund has failed
+        pub fn refund_contribution(&mut self, contribution_nft: NonFungibleBucket) -> Bucket {
+            assert!(self.is_failed(), "Crowdfund has not failed");
+            let (contribution_nft_id, amount) = self.burn_contribution_badge(contribution_nft);
+
+            let refund = self.contributions.take(amount);
+            Runtime::emit_event(CrowdfundContributionRefunded {
+                crowdfund_nft_id: self.crowdfund_nft_id.clone(),
+                contribution_nft_id,
+                amount,
+            });
+
+            refund
+        }
+
+        // Method allows the instantiator to recover the offered resources once the crowdfund has failed
+        pub fn recover_offered(&mut self, crowdfund_nft: NonFungibleProof) -> Vec<Bucket> {
+            self.verify_crowdfund_badge(crowdfund_nft);
+            assert!(self.is_failed(), "Crowdfund has not failed");
+
+            let recovered: Vec<Bucket> = self.offered_resources.values_mut().map(|vault| vault.take_all()).collect();
+            Runtime::emit_event(CrowdfundOfferRecovered {
+                crowdfund_nft_id: self.crowdfund_nft_id.clone(),
+                recovered: amounts_of(&recovered),
+            });
+
+            recovered
+        }
+
+        pub fn get_contributed_amount(&self) -> Decimal {
+            self.contributed_amount
+        }
+
+        pub fn get_contribution_count(&self) -> u64 {
+            self.contribution_count
+        }
+
+        pub fn get_target_amount(&self) -> Decimal {
+            self.target_amount
+        }
+
+        pub fn get_deadline_epoch(&self) -> u64 {
+            self.deadline_epoch
+        }
+
+        pub fn is_target_reached(&self) -> bool {
+            self.contributed_amount >= self.target_amount
+        }
+
+        // The deadline passed before the target was hit
+        pub fn is_failed(&self) -> bool {
+            !self.is_target_reached() && Runtime::current_epoch().number() >= self.deadline_epoch
+        }
+
+        // Burns a contribution badge of this crowdfund, returning its ID and the amount it stands for
+        fn burn_contribution_badge(&self, contribution_nft: NonFungibleBucket) -> (NonFungibleLocalId, Decimal) {
+            assert!(
+                contribution_nft.resource_address() == self.contribution_badge.address()
+                    && contribution_nft.amount() == Decimal::ONE,
+                "Invalid contribution badge"
+            );
+            let contribution_nft_id = contribution_nft.non_fungible_local_id();
+            let amount = contribution_nft.non_fungible::<ContributionBadge>().data().amount;
+            contribution_nft.burn();
+
+            (contribution_nft_id, amount)
+        }
+
+        fn verify_crowdfund_badge(&self, crowdfund_nft: NonFungibleProof) {
+            let crowdfund_nft = crowdfund_nft.check_with_message(self.crowdfund_nft, "Invalid Crowdfund NFT");
+            assert!(crowdfund_nft.contains_non_fungible(&self.crowdfund_nft_id), "Invalid Crowdfund NFT");
+        }
+    }
+}
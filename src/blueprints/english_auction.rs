@@ -0,0 +1,258 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::settlement::*;
+use crate::types::*;
+
+#[blueprint]
+#[events(AuctionBidPlaced, AuctionSettled, ReserveNotMet, AuctionExtended, AuctionBidReclaimed)]
+mod english_auction {
+    // Sells the offered resources to the highest bidder, holding the best bid in custody until settlement
+    struct EnglishAuction {
+        offered_resources: IndexMap<ResourceAddress, Vault>,
+        bid_resource: ResourceAddress,
+        min_bid: Decimal,
+        // Pushed back by late bids when the auction guards against sniping
+        end_epoch: u64,
+        anti_sniping: Option<AntiSniping>,
+        extended_epochs: u64,
+        reserve_hash: Option<Hash>,
+        min_increment: Option<BidIncrement>,
+        settlement_window_epochs: u64,
+        bid_vault: Vault,
+        highest_bidder: Option<ComponentAddress>,
+        // Refunds and winnings whose recipient account refused the deposit, waiting to be claimed
+        unclaimed: KeyValueStore<(ComponentAddress, ResourceAddress), Vault>,
+        auction_nft: ResourceAddress,
+        auction_nft_id: NonFungibleLocalId,
+        settled: bool,
+    }
+
+    impl EnglishAuction {
+
+        pub fn instantiate_english_auction(
+            offered_resources: Vec<Bucket>,
+            terms: EnglishAuctionTerms
+        ) -> (Global<EnglishAuction>, NonFungibleBucket) {
+            let EnglishAuctionTerms {
+                bid_resource,
+                min_bid,
+                end_epoch,
+                reserve_hash,
+                min_increment,
+                anti_sniping,
+                settlement_window_epochs,
+            } = terms;
+            assert!(!offered_resources.is_empty(), "At least one offered resource is required");
+            assert!(bid_resource.is_fungible(), "Bids have to be made in a fungible resource");
+            assert!(min_bid > Decimal::ZERO, "Minimum bid must be positive");
+            assert!(end_epoch > Runtime::current_epoch().number(), "End epoch must be in the future");
+            assert!(settlement_window_epochs > 0, "Settlement window has to last at least one epoch");
+            match min_increment {
+                Some(BidIncrement::Absolute { amount }) => assert!(amount > Decimal::ZERO, "Minimum increment must be positive"),
+                Some(BidIncrement::Relative { bps }) => assert!(bps > 0, "Minimum increment must be positive"),
+                None => {},
+            }
+            if let Some(anti_sniping) = anti_sniping {
+                assert!(
+                    anti_sniping.window_epochs > 0 && anti_sniping.extension_epochs > 0,
+                    "Anti-sniping window and extension must be positive"
+                );
+            }
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(EnglishAuction::blueprint_id());
+
+            // The auction is managed with the same badge as an escrow, asking for at least the minimum bid
+            let badge = escrow_badge_builder(component_address)
+                .mint_initial_supply([(1u64.into(), EscrowBadge {
+                    requested_resources: vec![crate::ResourceSpecifier::Fungible {
+                        resource_address: bid_resource,
+                        amount: min_bid,
+                    }],
+                    offered_resources: amounts_of(&offered_resources),
+                    created_epoch: Runtime::current_epoch().number(),
+                    expiry_epoch: Some(end_epoch),
+                    currency_prices: IndexMap::new(),
+                    escrow_component: component_address,
+                })]);
+
+            let component = Self {
+                offered_resources: vaults_of(offered_resources),
+                bid_resource,
+                min_bid,
+                end_epoch,
+                anti_sniping,
+                extended_epochs: 0,
+                reserve_hash,
+                min_increment,
+                settlement_window_epochs,
+                bid_vault: Vault::new(bid_resource),
+                highest_bidder: None,
+                unclaimed: KeyValueStore::new(),
+                auction_nft: badge.resource_address(),
+                auction_nft_id: badge.non_fungible_local_id(),
+                settled: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, badge)
+        }
+
+        // Computes the commitment of a reserve price, so the instantiator can hide it before instantiating
+        pub fn compute_reserve_commitment(reserve: Decimal, salt: String) -> Hash {
+            commitment_of(reserve, salt)
+        }
+
+        // Places a bid above the current best one, refunding the bidder it outbids
+        pub fn bid(&mut self, bid: Bucket, bidder: Global<Account>) {
+            assert!(!self.settled, "Auction has been settled");
+            assert!(Runtime::current_epoch().number() < self.end_epoch, "Auction has ended");
+            assert!(bid.resource_address() == self.bid_resource, "Invalid bid resource");
+            assert!(bid.amount() >= self.min_bid, "Bid is below the minimum bid");
+            assert!(bid.amount() > self.bid_vault.amount(), "Bid must be higher than the current best bid");
+            assert!(bid.amount() >= self.get_min_next_bid(), "Bid has to raise the best bid by the minimum increment");
+
+            let amount = bid.amount();
+            if let Some(previous_bidder) = self.highest_bidder {
+                let refund = self.bid_vault.take_all();
+                deposit_or_hold(&mut self.unclaimed, previous_bidder, refund);
+            }
+            self.bid_vault.put(bid);
+            self.highest_bidder = Some(bidder.address());
+
+            Runtime::emit_event(AuctionBidPlaced {
+                auction_nft_id: self.auction_nft_id.clone(),
+                bidder: bidder.address(),
+                amount,
+            });
+
+            self.extend_if_sniped();
+        }
+
+        // Method allows the instantiator to settle the auction once it has ended.
+        // The winner receives the offered resources, the instantiator gets the winning bid,
+        // or the offered resources back when nobody bid. A hidden reserve has to be revealed with its salt,
+        // and a best bid below it is refunded with the offered resources going back to the instantiator.
+        pub fn settle(&mut self, auction_nft: NonFungibleProof, reserve: Option<(Decimal, String)>) -> Vec<Bucket> {
+            self.verify_auction_badge(auction_nft);
+            assert!(!self.settled, "Auction has already been settled");
+            assert!(Runtime::current_epoch().number() >= self.end_epoch, "Auction has not ended yet");
+            self.settled = true;
+
+            let mut winning_bid = self.bid_vault.amount();
+            let offered: Vec<Bucket> = self.offered_resources.values_mut().map(|vault| vault.take_all()).collect();
+
+            if let (Some(reserve), Some(bidder)) = (reveal_reserve(self.reserve_hash, reserve), self.highest_bidder) {
+                if winning_bid < reserve {
+                    let refund = self.bid_vault.take_all();
+                    deposit_or_hold(&mut self.unclaimed, bidder, refund);
+                    self.highest_bidder = None;
+                    Runtime::emit_event(ReserveNotMet {
+                        auction_nft_id: self.auction_nft_id.clone(),
+                        reserve,
+                        best_bid: winning_bid,
+                    });
+                    winning_bid = Decimal::ZERO;
+                }
+            }
+
+            let proceeds = match self.highest_bidder {
+                Some(winner) => {
+                    for bucket in offered {
+                        deposit_or_hold(&mut self.unclaimed, winner, bucket);
+                    }
+                    vec![self.bid_vault.take_all()]
+                },
+                None => offered,
+            };
+
+            Runtime::emit_event(AuctionSettled {
+                auction_nft_id: self.auction_nft_id.clone(),
+                winner: self.highest_bidder,
+                winning_bid,
+            });
+
+            proceeds
+        }
+
+        // Returns the best bid to its bidder when the instantiator has not settled within the settlement window,
+        // anyone can trigger it. Settling afterwards returns the offered resources to the instantiator.
+        pub fn reclaim_bid(&mut self) {
+            assert!(!self.settled, "Auction has already been settled");
+            assert!(
+                Runtime::current_epoch().number() >= self.end_epoch + self.settlement_window_epochs,
+                "Settlement window has not closed yet"
+            );
+            let bidder = self.highest_bidder.take().expect("Auction has no bids");
+            let amount = self.bid_vault.amount();
+            let refund = self.bid_vault.take_all();
+            deposit_or_hold(&mut self.unclaimed, bidder, refund);
+
+            Runtime::emit_event(AuctionBidReclaimed {
+                auction_nft_id: self.auction_nft_id.clone(),
+                bidder,
+                amount,
+            });
+        }
+
+        // Delivers a held refund or winning to the account it belongs to, anyone can trigger it
+        pub fn claim(&mut self, account: Global<Account>, resource_address: ResourceAddress) {
+            claim_held(&mut self.unclaimed, account, resource_address);
+        }
+
+        pub fn get_highest_bid(&self) -> Option<(ComponentAddress, Decimal)> {
+            self.highest_bidder.map(|bidder| (bidder, self.bid_vault.amount()))
+        }
+
+        // Lowest bid that can be placed right now
+        pub fn get_min_next_bid(&self) -> Decimal {
+            if self.highest_bidder.is_none() {
+                return self.min_bid;
+            }
+            let best_bid = self.bid_vault.amount();
+            match self.min_increment {
+                Some(BidIncrement::Absolute { amount }) => best_bid + amount,
+                Some(BidIncrement::Relative { bps }) => best_bid + fee_amount(best_bid, bps),
+                None => best_bid,
+            }
+        }
+
+        pub fn get_end_epoch(&self) -> u64 {
+            self.end_epoch
+        }
+
+        pub fn is_settled(&self) -> bool {
+            self.settled
+        }
+
+        // Extends the auction when a bid lands within its final window, as far as the maximum extension allows
+        fn extend_if_sniped(&mut self) {
+            let Some(anti_sniping) = self.anti_sniping else {
+                return;
+            };
+            if Runtime::current_epoch().number() + anti_sniping.window_epochs < self.end_epoch {
+                return;
+            }
+            let extension = anti_sniping.extension_epochs.min(anti_sniping.max_extension_epochs - self.extended_epochs);
+            if extension == 0 {
+                return;
+            }
+            self.end_epoch += extension;
+            self.extended_epochs += extension;
+
+            Runtime::emit_event(AuctionExtended {
+                auction_nft_id: self.auction_nft_id.clone(),
+                end_epoch: self.end_epoch,
+            });
+        }
+
+        fn verify_auction_badge(&self, auction_nft: NonFungibleProof) {
+            let auction_nft = auction_nft.check_with_message(self.auction_nft, "Invalid Auction NFT");
+            assert!(auction_nft.contains_non_fungible(&self.auction_nft_id), "Invalid Auction NFT");
+        }
+    }
+}
@@ -0,0 +1,893 @@
+use scrypto::prelude::*;
+
+use crate::client::ESCROW_INTERFACE_VERSION;
+use crate::FeeConfig;
+use crate::escrow::*;
+use crate::settlement::*;
+use crate::types::*;
+use crate::types::ResourceSpecifier;
+
+#[blueprint]
+#[events(
+    EscrowInstantiated, EscrowFilled, EscrowCancelled, EscrowWithdrawn,
+    CounterOfferProposed, CounterOfferAccepted, CounterOfferRejected,
+    DisputeRaised, DisputeVoteCast, DisputeResolved, SecretRevealed, UnsoldWithdrawn,
+    OfferedAdded, RequestedUpdated, AlternativeChosen, EscrowPaused, EscrowUnpaused,
+    BadgeRecoveryRequested, BadgeRecoveryCancelled, BadgeRecovered, EscrowOwnershipTransferred,
+    BeneficiaryPaid, ExpiredReclaimed, ReferralFeeAccrued, CancellationPenaltyPaid,
+    TakerLegDeposited, TakerLegWithdrawn, ApprovalRequested, FillApproved, UnapprovedRefunded,
+    SettlementClaimed, BondPosted, BondSlashed, BondWithdrawn, PremiumPaid, CoverageUnderwritten, CoveragePaidOut,
+    PaymentConverted, TakerAllowlistUpdated, EscrowSoldOut, EscrowClosed,
+    DeadlineExtended, EscrowFractionalized, MakerSharesClaimed,
+    ProceedsPaidOut, CreatorRoyaltyAccrued
+)]
+mod escrow {
+    // Our own specifier, not the one re-exported by the scrypto prelude
+    use super::ResourceSpecifier;
+
+    // Managing the escrow is reserved to the holder of its EscrowBadge, everything else is open to anyone.
+    // The component moves the maker role over to a new badge when ownership is transferred.
+    enable_method_auth! {
+        roles {
+            maker => updatable_by: [SELF];
+        },
+        methods {
+            exchange => PUBLIC;
+            exchange_with_secret => PUBLIC;
+            exchange_with_terms => PUBLIC;
+            exchange_with_referrer => PUBLIC;
+            exchange_with_credential => PUBLIC;
+            exchange_as => PUBLIC;
+            exchange_with_max_price => PUBLIC;
+            exchange_fee_exempt => PUBLIC;
+            claim_fees => PUBLIC;
+            claim_referral_fees => PUBLIC;
+            claim_payout => PUBLIC;
+            deliver_payout => PUBLIC;
+            claim_creator_royalties => PUBLIC;
+            swap_with => PUBLIC;
+            deposit_taker_leg => PUBLIC;
+            withdraw_taker_leg => PUBLIC;
+            settle => PUBLIC;
+            request_approval => PUBLIC;
+            approve => restrict_to: [maker];
+            refund_unapproved => PUBLIC;
+            withdraw_resource => restrict_to: [maker];
+            claim => PUBLIC;
+            update_requested => restrict_to: [maker];
+            extend_deadline => restrict_to: [maker];
+            add_offered => restrict_to: [maker];
+            withdraw_unsold => restrict_to: [maker];
+            cancel_escrow => restrict_to: [maker];
+            propose_counter_offer => PUBLIC;
+            accept_counter_offer => restrict_to: [maker];
+            reject_counter_offer => restrict_to: [maker];
+            get_counter_offer => PUBLIC;
+            claim_royalties => restrict_to: [maker];
+            transfer_ownership => PUBLIC;
+            close => PUBLIC;
+            fractionalize => PUBLIC;
+            claim_share => PUBLIC;
+            add_allowed_taker => restrict_to: [maker];
+            remove_allowed_taker => restrict_to: [maker];
+            pause => restrict_to: [OWNER];
+            unpause => restrict_to: [OWNER];
+            request_badge_recovery => PUBLIC;
+            cancel_badge_recovery => restrict_to: [maker];
+            recover_badge => PUBLIC;
+            observe_price => PUBLIC;
+            refund_expired => PUBLIC;
+            pay_beneficiary => PUBLIC;
+            reclaim_expired => PUBLIC;
+            raise_dispute => PUBLIC;
+            resolve_dispute => PUBLIC;
+            claim_refund => PUBLIC;
+            post_maker_bond => restrict_to: [maker];
+            post_taker_bond => PUBLIC;
+            withdraw_maker_bond => restrict_to: [maker];
+            withdraw_taker_bond => PUBLIC;
+            claim_arbiter_bond_cut => PUBLIC;
+            pay_premium => PUBLIC;
+            withdraw_premium => PUBLIC;
+            underwrite => PUBLIC;
+            claim_coverage => PUBLIC;
+            withdraw_coverage => PUBLIC;
+            get_details => PUBLIC;
+            get_status => PUBLIC;
+            get_remaining_amount => PUBLIC;
+            get_required_payment => PUBLIC;
+            preview_exchange => PUBLIC;
+            get_current_price => PUBLIC;
+            get_unit_price => PUBLIC;
+            get_current_tranche => PUBLIC;
+            get_taker_filled_amount => PUBLIC;
+            is_price_fresh => PUBLIC;
+            is_expired => PUBLIC;
+            is_closed => PUBLIC;
+            get_interface_version => PUBLIC;
+        }
+    }
+
+    struct Escrow {
+        entry: EscrowEntry,
+        escrow_nft: ResourceAddress,
+        badge_id_kind: BadgeIdKind,
+        fee_config: Option<Global<FeeConfig>>,
+        max_fee_bps: u16,
+        // Fees the recipient of the fee config refused, waiting for `claim_fees`
+        fee_vaults: KeyValueStore<ResourceAddress, Vault>,
+        // Number of EscrowBadges minted so far, the next one is numbered after it
+        badge_count: u64,
+        // Fungible shares the escrow is owned by once fractionalized
+        maker_shares: Option<ResourceAddress>,
+        // Everything the fractionalized escrow held when it ended, waiting for the share holders
+        share_pool: IndexMap<ResourceAddress, Vault>,
+    }
+
+    impl Escrow {
+
+        // The exchange royalty is charged to takers on every exchange call, on top of their payment
+        pub fn instantiate_escrow(
+            requested_resources: Vec<ResourceSpecifier>,
+            offered_resources: Vec<Bucket>,
+            config: EscrowConfig,
+            exchange_royalty: RoyaltyAmount
+        ) -> (Global<Escrow>, NonFungibleBucket) {
+
+            // Reserving the component address up front so the component itself can be the badge authority
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(Escrow::blueprint_id());
+
+            // Minting the EscrowBadge NFT which will be used to manage the escrow.
+            let badge_id_kind = config.badge_id_kind.clone();
+            let fee_config = config.fee_config.map(Global::<FeeConfig>::from);
+            let max_fee_bps = config.max_fee_bps;
+            let badge = mint_first_escrow_badge(component_address, &badge_id_kind, config.soulbound_badge, EscrowBadge {
+                requested_resources: requested_resources.clone(),
+                offered_resources: amounts_of(&offered_resources),
+                created_epoch: Runtime::current_epoch().number(),
+                expiry_epoch: config.expiry_epoch,
+                currency_prices: config.currency_prices.clone(),
+                escrow_component: component_address,
+            });
+
+            // Takers of arbitrated escrows get a badge to raise disputes and claim refunds with
+            let taker_badge = taker_badge_builder(component_address).create_with_no_initial_supply();
+            let taker_receipt = config.taker_receipts.map(|taker_receipts| {
+                taker_receipt_builder(component_address, taker_receipts).create_with_no_initial_supply().address()
+            });
+            let reputation_attestation = config.reputation_attestations.then(|| {
+                reputation_attestation_builder(component_address).create_with_no_initial_supply().address()
+            });
+            let settlement_claim = config.claim_settlement.then(|| {
+                settlement_claim_builder(component_address).create_with_no_initial_supply().address()
+            });
+
+            let entry = EscrowEntry::new(
+                badge.non_fungible_local_id(),
+                requested_resources,
+                offered_resources,
+                config,
+                EscrowResources {
+                    taker_badge: taker_badge.address(),
+                    taker_receipt,
+                    reputation_attestation,
+                    settlement_claim,
+                },
+            );
+
+            // The EscrowBadge owns the component and fills the maker role
+            let maker_rule = Self::maker_rule(badge.resource_address(), badge.non_fungible_local_id());
+
+            // Instntianting the Escrow component with the initial state
+            let component = Self {
+                entry,
+                escrow_nft: badge.resource_address(),
+                badge_id_kind,
+                fee_config,
+                max_fee_bps,
+                fee_vaults: KeyValueStore::new(),
+                badge_count: 1,
+                maker_shares: None,
+                share_pool: IndexMap::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Updatable(Self::owner_rule(
+                badge.resource_address(),
+                badge.non_fungible_local_id(),
+                component_address,
+            )))
+            .roles(roles! {
+                maker => maker_rule;
+            })
+            .with_address(address_reservation)
+            .enable_component_royalties(component_royalties! {
+                // Royalties are fixed at instantiation and only the component itself can claim them
+                roles {
+                    royalty_setter => rule!(deny_all);
+                    royalty_setter_updater => rule!(deny_all);
+                    royalty_locker => rule!(deny_all);
+                    royalty_locker_updater => rule!(deny_all);
+                    royalty_claimer => rule!(require(global_caller(component_address)));
+                    royalty_claimer_updater => rule!(deny_all);
+                },
+                init {
+                    exchange => exchange_royalty, locked;
+                    exchange_with_secret => exchange_royalty, locked;
+                    exchange_with_terms => exchange_royalty, locked;
+                    exchange_with_referrer => exchange_royalty, locked;
+                    exchange_with_credential => exchange_royalty, locked;
+                    exchange_as => exchange_royalty, locked;
+                    exchange_with_max_price => exchange_royalty, locked;
+                    exchange_fee_exempt => exchange_royalty, locked;
+                    claim_fees => Free, locked;
+                    deposit_taker_leg => exchange_royalty, locked;
+                    withdraw_taker_leg => Free, locked;
+                    settle => Free, locked;
+                    request_approval => exchange_royalty, locked;
+                    approve => Free, locked;
+                    refund_unapproved => Free, locked;
+                    claim => Free, locked;
+                    claim_referral_fees => Free, locked;
+                    claim_payout => Free, locked;
+                    deliver_payout => Free, locked;
+                    claim_creator_royalties => Free, locked;
+                    observe_price => Free, locked;
+                    refund_expired => Free, locked;
+                    pay_beneficiary => Free, locked;
+                    reclaim_expired => Free, locked;
+                    swap_with => exchange_royalty, locked;
+                    withdraw_resource => Free, locked;
+                    cancel_escrow => Free, locked;
+                    withdraw_unsold => Free, locked;
+                    add_offered => Free, locked;
+                    update_requested => Free, locked;
+                    extend_deadline => Free, locked;
+                    propose_counter_offer => Free, locked;
+                    accept_counter_offer => Free, locked;
+                    reject_counter_offer => Free, locked;
+                    get_counter_offer => Free, locked;
+                    claim_royalties => Free, locked;
+                    transfer_ownership => Free, locked;
+                    close => Free, locked;
+                    fractionalize => Free, locked;
+                    claim_share => Free, locked;
+                    add_allowed_taker => Free, locked;
+                    remove_allowed_taker => Free, locked;
+                    pause => Free, locked;
+                    unpause => Free, locked;
+                    request_badge_recovery => Free, locked;
+                    cancel_badge_recovery => Free, locked;
+                    recover_badge => Free, locked;
+                    raise_dispute => Free, locked;
+                    resolve_dispute => Free, locked;
+                    claim_refund => Free, locked;
+                    post_maker_bond => Free, locked;
+                    post_taker_bond => Free, locked;
+                    withdraw_maker_bond => Free, locked;
+                    withdraw_taker_bond => Free, locked;
+                    claim_arbiter_bond_cut => Free, locked;
+                    pay_premium => Free, locked;
+                    withdraw_premium => Free, locked;
+                    underwrite => Free, locked;
+                    claim_coverage => Free, locked;
+                    withdraw_coverage => Free, locked;
+                    get_details => Free, locked;
+                    get_status => Free, locked;
+                    get_remaining_amount => Free, locked;
+                    get_current_price => Free, locked;
+                    get_unit_price => Free, locked;
+                    get_current_tranche => Free, locked;
+                    get_taker_filled_amount => Free, locked;
+                    get_required_payment => Free, locked;
+                    preview_exchange => Free, locked;
+                    is_price_fresh => Free, locked;
+                    is_expired => Free, locked;
+                    is_closed => Free, locked;
+                    get_interface_version => Free, locked;
+                }
+            })
+            .globalize();
+
+            // We have to return the instantiated component and the minted badge
+            (component, badge)
+
+        }
+
+        // Returns the offered resources together with any change left over from the payment
+        pub fn exchange(&mut self, payment: Vec<Bucket>) -> (Vec<Bucket>, Vec<Bucket>) {
+            self.exchange_entry(payment, TakerInput::default(), self.fee_bps())
+        }
+
+        // Method for takers of hash-locked or invite-only escrows, supplying the secret the escrow is locked with
+        pub fn exchange_with_secret(&mut self, payment: Vec<Bucket>, secret: Vec<u8>) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { secret: Some(secret), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for takers of terms-bound escrows, supplying the hash of the terms they agreed to
+        pub fn exchange_with_terms(&mut self, payment: Vec<Bucket>, terms_hash: Hash) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { terms_hash: Some(terms_hash), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for takers sent by a frontend or other referrer, which earns the referral fee of the fill
+        pub fn exchange_with_referrer(&mut self, payment: Vec<Bucket>, referrer: ResourceAddress) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { referrer: Some(referrer), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for takers of regulated escrows, presenting a proof of their compliance credential
+        pub fn exchange_with_credential(
+            &mut self,
+            payment: Vec<Bucket>,
+            credential: NonFungibleProof
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { credential: Some(credential), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for allowlisted takers filling from their account, whose owner has to be present
+        pub fn exchange_as(&mut self, payment: Vec<Bucket>, account: ComponentAddress) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { account: Some(account), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for takers of oracle-priced escrows, aborting the fill if the price has risen above their maximum
+        pub fn exchange_with_max_price(&mut self, payment: Vec<Bucket>, max_price: Decimal) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { max_price: Some(max_price), ..Default::default() };
+            self.exchange_entry(payment, taker, self.fee_bps())
+        }
+
+        // Method for takers holding a badge the fee config exempts from the protocol fee
+        pub fn exchange_fee_exempt(&mut self, payment: Vec<Bucket>, exemption: Proof) -> (Vec<Bucket>, Vec<Bucket>) {
+            let fee_config = self.fee_config.expect("Escrow has no fee config");
+            assert!(fee_config.is_fee_exempt(exemption.resource_address()), "Invalid fee exemption badge");
+            self.exchange_entry(payment, TakerInput::default(), 0)
+        }
+
+        // Method delivers the fees the recipient of the fee config refused to its current recipient, anyone can trigger it
+        pub fn claim_fees(&mut self, resource_address: ResourceAddress) {
+            let fee_config = self.fee_config.expect("Escrow has no fee config");
+            let fees = self.fee_vaults
+                .get_mut(&resource_address)
+                .expect("No fees held in this resource")
+                .take_all();
+            let mut fee_recipient: Global<Account> = fee_config.get_fee_recipient().into();
+            fee_recipient.try_deposit_or_abort(fees, None);
+        }
+
+        // Method allows a referrer to claim its referral fees with a proof of its resource
+        pub fn claim_referral_fees(&mut self, referrer: Proof) -> Vec<Bucket> {
+            // Any genuine proof will do, it is the resource behind it that identifies the referrer
+            self.entry.claim_referral_fees(referrer.resource_address())
+        }
+
+        // Method pays a badge recipient of the payout table its slices of the withdrawn proceeds
+        pub fn claim_payout(&mut self, recipient: Proof) -> Vec<Bucket> {
+            // As with referrers, it is the resource behind the proof that identifies the recipient
+            self.entry.claim_payout(PayoutRecipient::Badge(recipient.resource_address()))
+        }
+
+        // Method delivers the payout an account recipient refused to that account, anyone can trigger it
+        pub fn deliver_payout(&mut self, account: ComponentAddress) {
+            let payout = self.entry.claim_payout(PayoutRecipient::Account(account));
+            let mut account: Global<Account> = account.into();
+            account.try_deposit_batch_or_abort(payout, None);
+        }
+
+        // Method pays the creator of the offered NFTs the royalties collected from the fills
+        pub fn claim_creator_royalties(&mut self, creator: Proof) -> Vec<Bucket> {
+            self.entry.claim_creator_royalties(creator.resource_address())
+        }
+
+        // Method settles this escrow against a complementary one in a single step,
+        // returning the surplus left over on both sides to the caller
+        pub fn swap_with(&mut self, other: Global<Escrow>) -> Vec<Bucket> {
+            assert!(other.address() != Runtime::global_address(), "An escrow cannot be swapped with itself");
+            self.entry.swap(|offered| other.exchange(offered))
+        }
+
+        // Method for the taker of a two-sided escrow to put their leg in ahead of settlement,
+        // returning a TakerBadge to back out with and the rest of the payment
+        pub fn deposit_taker_leg(&mut self, payment: Vec<Bucket>, taker_account: ComponentAddress) -> (Bucket, Vec<Bucket>) {
+            self.entry.deposit_taker_leg(payment, taker_account)
+        }
+
+        // Method allows the taker of a two-sided escrow to back out before settlement
+        pub fn withdraw_taker_leg(&mut self, taker_nft: NonFungibleProof) -> Vec<Bucket> {
+            self.verify_taker_badge(taker_nft);
+            self.entry.withdraw_taker_leg()
+        }
+
+        // Method swaps both legs of a two-sided escrow at once, anyone can trigger it once both are in
+        pub fn settle(&mut self) {
+            self.entry.settle();
+        }
+
+        // Method for takers of escrows that vet their counterparty. The payment is held until the instantiator
+        // approves the returned claim badge, and refunded to the taker's account if they don't in time.
+        pub fn request_approval(&mut self, payment: Vec<Bucket>, taker_account: ComponentAddress) -> (Bucket, Vec<Bucket>) {
+            self.entry.request_approval(payment, taker_account)
+        }
+
+        // Method allows the instantiator to accept the taker holding the given claim badge
+        pub fn approve(&mut self, taker_claim: NonFungibleLocalId) {
+            self.entry.approve(taker_claim);
+        }
+
+        pub fn refund_unapproved(&mut self) {
+            self.entry.refund_unapproved();
+        }
+
+        // Method allows the instantiator to withdraw their requested resources
+        // The maker role only needs a proof of the EscrowBadge in the auth zone, so it can stay in the instantiator's account
+        pub fn withdraw_resource(&mut self) -> Vec<Bucket> {
+            // Returns the requested resources to the instantiator
+            self.entry.withdraw()
+        }
+
+        // Method redeems a settlement claim, minted instead of the resources by claim-settled escrows, for what it is owed
+        pub fn claim(&mut self, claim_nft: NonFungibleBucket) -> Vec<Bucket> {
+            self.entry.claim(claim_nft)
+        }
+
+        // Method allows the instantiator to reprice the escrow before anything has been paid into it
+        pub fn update_requested(&mut self, requested_resources: Vec<ResourceSpecifier>) {
+            self.entry.update_requested(requested_resources);
+            self.sync_badge();
+        }
+
+        // Method allows the instantiator to keep the escrow listed for longer, under the same badge and address
+        pub fn extend_deadline(&mut self, deadline: Deadline) {
+            self.entry.extend_deadline(deadline);
+            self.sync_badge();
+        }
+
+        // Method allows the instantiator to top up an offered resource while keeping the same badge and address
+        pub fn add_offered(&mut self, bucket: Bucket) {
+            self.entry.add_offered(bucket);
+        }
+
+        // Method allows the instantiator of a partially fillable escrow to pull back part of what is still unsold
+        pub fn withdraw_unsold(&mut self, retired_amount: Decimal) -> Vec<Bucket> {
+            let returned = self.entry.withdraw_unsold(retired_amount);
+            self.sync_badge();
+            returned
+        }
+
+        pub fn cancel_escrow(&mut self) -> Vec<Bucket> {
+            // Return the offered resources to the instantiator
+            // This ensures that the instantiator gets back their resources
+            self.entry.cancel()
+        }
+
+        // Method for a prospective taker to propose alternative requested terms
+        pub fn propose_counter_offer(
+            &mut self,
+            requested_resources: Vec<ResourceSpecifier>,
+            proposer: Option<ComponentAddress>
+        ) -> u64 {
+            self.entry.propose_counter_offer(requested_resources, proposer)
+        }
+
+        // Method allows the instantiator to take a counter-offer as the new requested terms
+        pub fn accept_counter_offer(&mut self, counter_offer_id: u64) {
+            self.entry.accept_counter_offer(counter_offer_id);
+            self.sync_badge();
+        }
+
+        pub fn reject_counter_offer(&mut self, counter_offer_id: u64) {
+            self.entry.reject_counter_offer(counter_offer_id);
+        }
+
+        pub fn get_counter_offer(&self, counter_offer_id: u64) -> Option<CounterOffer> {
+            self.entry.counter_offer(counter_offer_id)
+        }
+
+        // Method allows the instantiator to claim the royalties accrued by exchange calls
+        pub fn claim_royalties(&mut self) -> Bucket {
+            Runtime::global_component().claim_component_royalties()
+        }
+
+        // Method allows the instantiator to let another account or component fill an allowlisted escrow
+        pub fn add_allowed_taker(&mut self, taker: ComponentAddress) {
+            self.entry.set_taker_allowed(taker, true);
+        }
+
+        pub fn remove_allowed_taker(&mut self, taker: ComponentAddress) {
+            self.entry.set_taker_allowed(taker, false);
+        }
+
+        // Method is the emergency brake of the owner, blocking fills until the escrow is unpaused
+        pub fn pause(&mut self) {
+            self.entry.set_paused(true);
+        }
+
+        pub fn unpause(&mut self) {
+            self.entry.set_paused(false);
+        }
+
+        // Method hands the escrow over to a new manager, by burning the EscrowBadge and minting a fresh one
+        pub fn transfer_ownership(&mut self, escrow_nft: NonFungibleBucket) -> NonFungibleBucket {
+            assert!(
+                escrow_nft.resource_address() == self.escrow_nft
+                    && escrow_nft.amount() == Decimal::ONE
+                    && escrow_nft.contains_non_fungible(&self.entry.escrow_nft_id),
+                "Invalid Escrow NFT"
+            );
+
+            let badge_data = escrow_nft.non_fungible::<EscrowBadge>().data();
+            escrow_nft.burn();
+            self.badge_count += 1;
+            let badge = mint_escrow_badge(self.escrow_nft.into(), &self.badge_id_kind, self.badge_count, badge_data);
+            self.entry.reissue_badge(badge.non_fungible_local_id());
+
+            // Only the new badge manages and owns the component from now on
+            let escrow_nft_id = badge.non_fungible_local_id();
+            let component = Runtime::global_component();
+            component.set_role("maker", Self::maker_rule(self.escrow_nft, escrow_nft_id.clone()));
+            component.set_owner_role(Self::owner_rule(self.escrow_nft, escrow_nft_id, Runtime::global_address()));
+
+            badge
+        }
+
+        // Method allows the instantiator to wind the escrow up for good, returning the remaining offered resources
+        // and the collected payment in exchange for the burnt EscrowBadge
+        pub fn close(&mut self, escrow_nft: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                escrow_nft.resource_address() == self.escrow_nft
+                    && escrow_nft.amount() == Decimal::ONE
+                    && escrow_nft.contains_non_fungible(&self.entry.escrow_nft_id),
+                "Invalid Escrow NFT"
+            );
+            escrow_nft.burn();
+            self.entry.close()
+        }
+
+        // Method hands the escrow over to fungible maker shares, e.g. for a syndicate that co-funded the offer.
+        // The EscrowBadge is burnt, and once the escrow has ended the share holders claim what it holds pro rata,
+        // along with the exchange royalties it collected.
+        pub fn fractionalize(&mut self, escrow_nft: NonFungibleBucket, supply: Decimal) -> FungibleBucket {
+            assert!(
+                escrow_nft.resource_address() == self.escrow_nft
+                    && escrow_nft.amount() == Decimal::ONE
+                    && escrow_nft.contains_non_fungible(&self.entry.escrow_nft_id),
+                "Invalid Escrow NFT"
+            );
+            assert!(supply > Decimal::ZERO, "Share supply must be positive");
+            self.entry.assert_fractionalizable();
+            escrow_nft.burn();
+
+            // Only the component can burn shares, which it does as they are claimed
+            let component_address = Runtime::global_address();
+            let shares = ResourceBuilder::new_fungible(OwnerRole::None)
+                .metadata(metadata! {
+                    init {
+                        "name" => "Scrypto 101 Escrow Maker Share", locked;
+                        "escrow_component" => component_address, locked;
+                    }
+                })
+                .burn_roles(burn_roles! {
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                })
+                .mint_initial_supply(supply);
+            self.maker_shares = Some(shares.resource_address());
+
+            // Nobody manages the escrow from now on, while holders of half the shares keep the emergency brake
+            let component = Runtime::global_component();
+            component.set_role("maker", rule!(deny_all));
+            let owner_amount = supply / 2;
+            component.set_owner_role(rule!(
+                require_amount(owner_amount, shares.resource_address()) || require(global_caller(component_address))
+            ));
+
+            Runtime::emit_event(EscrowFractionalized {
+                escrow_nft_id: self.entry.escrow_nft_id.clone(),
+                maker_shares: shares.resource_address(),
+                supply,
+            });
+            shares
+        }
+
+        // Method pays the holder of maker shares their part of everything the fractionalized escrow held when it
+        // ended, burning the shares. The first claim winds the escrow up.
+        pub fn claim_share(&mut self, shares: FungibleBucket) -> Vec<Bucket> {
+            let maker_shares = self.maker_shares.expect("Escrow has not been fractionalized");
+            assert!(shares.resource_address() == maker_shares, "Invalid maker shares");
+
+            if self.entry.status() != EscrowStatus::Closed {
+                assert!(
+                    matches!(self.entry.status(), EscrowStatus::Filled | EscrowStatus::SoldOut | EscrowStatus::Expired),
+                    "Escrow has not ended yet"
+                );
+                let mut pooled = self.entry.close();
+                pooled.push(Runtime::global_component().claim_component_royalties());
+                for bucket in pooled {
+                    match self.share_pool.get_mut(&bucket.resource_address()) {
+                        Some(vault) => vault.put(bucket),
+                        None => {
+                            self.share_pool.insert(bucket.resource_address(), Vault::with_bucket(bucket));
+                        },
+                    }
+                }
+            }
+
+            // Burnt shares leave the supply, so each claim is a slice of what the outstanding shares are owed
+            let supply = ResourceManager::from(maker_shares).total_supply().expect("Share supply is not tracked");
+            let claimed: Vec<Bucket> = self.share_pool
+                .values_mut()
+                .map(|vault| {
+                    let share = proportional_amount(vault.amount(), shares.amount(), supply);
+                    vault.take_advanced(share, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .collect();
+
+            Runtime::emit_event(MakerSharesClaimed {
+                escrow_nft_id: self.entry.escrow_nft_id.clone(),
+                shares: shares.amount(),
+                claimed: amounts_of(&claimed),
+            });
+            shares.burn();
+            claimed
+        }
+
+        // Method for the recovery authority to announce it will recall a lost EscrowBadge once the delay has passed
+        pub fn request_badge_recovery(&mut self, authority: Proof) {
+            self.entry.request_badge_recovery(authority);
+        }
+
+        // Method allows an instantiator who still holds the badge to stop a recovery
+        pub fn cancel_badge_recovery(&mut self) {
+            self.entry.cancel_badge_recovery();
+        }
+
+        // Method recalls the EscrowBadge from the vault it was lost in and hands it to the recovery authority,
+        // which passes it on to the instantiator
+        pub fn recover_badge(&mut self, authority: Proof, badge_vault: InternalAddress) -> NonFungibleBucket {
+            self.entry.complete_badge_recovery(authority);
+
+            let output = ScryptoVmV1Api::object_call_direct(
+                badge_vault.as_node_id(),
+                VAULT_RECALL_IDENT,
+                scrypto_encode(&VaultRecallInput { amount: Decimal::ONE }).unwrap(),
+            );
+            let badge: Bucket = scrypto_decode(&output).unwrap();
+            let badge = badge.as_non_fungible();
+            assert!(
+                badge.resource_address() == self.escrow_nft && badge.contains_non_fungible(&self.entry.escrow_nft_id),
+                "Vault does not hold the EscrowBadge"
+            );
+            badge
+        }
+
+        // Method records the oracle price for a TWAP-guarded escrow that keeps its own observations, anyone can trigger it
+        pub fn observe_price(&mut self) {
+            self.entry.observe_price();
+        }
+
+        // Method returns the offered resources of an expired hash-locked escrow to its refund account, anyone can trigger it
+        pub fn refund_expired(&mut self) {
+            self.entry.refund_expired();
+        }
+
+        // Method pays the offered resources of an escrow left expired for too long out to its beneficiary
+        pub fn pay_beneficiary(&mut self) {
+            self.entry.pay_beneficiary();
+        }
+
+        // Method returns the offered resources of an expired escrow to the instantiator, and the bounty to the caller
+        pub fn reclaim_expired(&mut self) -> Vec<Bucket> {
+            self.entry.reclaim_expired()
+        }
+
+        // Method allows the instantiator or the taker of an arbitrated escrow to dispute the fill
+        pub fn raise_dispute(&mut self, party: NonFungibleProof) {
+            self.verify_party(party);
+            self.entry.raise_dispute();
+        }
+
+        // Method allows the arbiter to award the collected payment of a disputed escrow to either side.
+        // With an arbiter council this casts one vote, and the award applies once enough arbiters agree.
+        pub fn resolve_dispute(&mut self, arbiter: Proof, award: DisputeAward) {
+            self.entry.resolve_dispute(arbiter, award);
+        }
+
+        // Method allows the taker to claim the payment the arbiter awarded back to them
+        pub fn claim_refund(&mut self, taker_nft: NonFungibleProof) -> Vec<Bucket> {
+            self.verify_taker_badge(taker_nft);
+            self.entry.claim_refund()
+        }
+
+        // Method allows the instantiator to back an arbitrated escrow with a collateral bond
+        pub fn post_maker_bond(&mut self, bond: Bucket) {
+            self.entry.post_bond(SettlementParty::Maker, bond);
+        }
+
+        // Method allows the taker to back their fill with a collateral bond
+        pub fn post_taker_bond(&mut self, taker_nft: NonFungibleProof, bond: Bucket) {
+            self.verify_taker_badge(taker_nft);
+            self.entry.post_bond(SettlementParty::Taker, bond);
+        }
+
+        pub fn withdraw_maker_bond(&mut self) -> Bucket {
+            self.entry.withdraw_bond(SettlementParty::Maker)
+        }
+
+        pub fn withdraw_taker_bond(&mut self, taker_nft: NonFungibleProof) -> Bucket {
+            self.verify_taker_badge(taker_nft);
+            self.entry.withdraw_bond(SettlementParty::Taker)
+        }
+
+        // Method allows the arbiter to collect their cut of the bonds slashed by their verdicts
+        pub fn claim_arbiter_bond_cut(&mut self, arbiter: Proof) -> Bucket {
+            self.entry.claim_arbiter_bond_cut(arbiter)
+        }
+
+        // Method allows the insured party, presenting their EscrowBadge or Taker NFT, to pay the insurance premium
+        pub fn pay_premium(&mut self, party: NonFungibleProof, premium: Bucket) {
+            let party = self.verify_party(party);
+            self.entry.pay_premium(party, premium);
+        }
+
+        pub fn withdraw_premium(&mut self, party: NonFungibleProof) -> Bucket {
+            let party = self.verify_party(party);
+            self.entry.withdraw_premium(party)
+        }
+
+        // Method allows the insurer to deposit the coverage, collecting the premium in return
+        pub fn underwrite(&mut self, insurer: Proof, coverage: Bucket) -> Bucket {
+            self.entry.underwrite(insurer, coverage)
+        }
+
+        pub fn claim_coverage(&mut self, party: NonFungibleProof) -> Bucket {
+            let party = self.verify_party(party);
+            self.entry.claim_coverage(party)
+        }
+
+        pub fn withdraw_coverage(&mut self, insurer: Proof) -> Bucket {
+            self.entry.withdraw_coverage(insurer)
+        }
+
+        // Method to read the current state of the escrow in one call
+        pub fn get_details(&self) -> EscrowDetails {
+            self.entry.details(self.escrow_nft)
+        }
+
+        // Method to read the lifecycle status, reporting open escrows past their deadline as expired
+        pub fn get_status(&self) -> EscrowStatus {
+            self.entry.status()
+        }
+
+        // Method to check how much of a requested fungible amount is still unfilled
+        pub fn get_remaining_amount(&self, resource_address: ResourceAddress) -> Decimal {
+            self.entry.remaining_amount(resource_address)
+        }
+
+        // Method to quote the payment that releases the given amount of an offered resource
+        pub fn get_required_payment(&self, resource_address: ResourceAddress, amount: Decimal) -> RequiredPayment {
+            self.entry.required_payment(resource_address, amount, self.fee_bps())
+        }
+
+        // Method to dry-run a fill with the given amount of a resource, without changing any state
+        pub fn preview_exchange(&self, resource_address: ResourceAddress, amount: Decimal) -> ExchangePreview {
+            self.entry.preview_exchange(resource_address, amount, self.fee_bps())
+        }
+
+        // Method to read the amount a Dutch auction, oracle-priced or per-unit escrow asks for right now
+        pub fn get_current_price(&self) -> Option<Decimal> {
+            self.entry.current_price()
+        }
+
+        // Method to quote the price of the next offered unit of a per-unit escrow, e.g. along its bonding curve
+        pub fn get_unit_price(&self) -> Decimal {
+            self.entry.unit_price()
+        }
+
+        pub fn get_current_tranche(&self) -> u32 {
+            self.entry.current_tranche()
+        }
+
+        // Method to check how much a taker has paid into an escrow with a per-taker cap
+        pub fn get_taker_filled_amount(&self, taker_id: TakerId) -> Decimal {
+            self.entry.taker_filled_amount(&taker_id)
+        }
+
+        // Method to check whether the oracle price of an oracle-priced escrow is recent enough to fill at
+        pub fn is_price_fresh(&self) -> bool {
+            self.entry.is_price_fresh()
+        }
+
+        // Method to check whether the escrow deadline has passed
+        pub fn is_expired(&self) -> bool {
+            self.entry.is_expired()
+        }
+
+        // Method to check whether the escrow can no longer be filled
+        pub fn is_closed(&self) -> bool {
+            self.entry.is_closed()
+        }
+
+        // Method to read the version of the external call interface this escrow implements
+        pub fn get_interface_version(&self) -> u32 {
+            ESCROW_INTERFACE_VERSION
+        }
+
+        // A standalone escrow charges no protocol fee, unless it reads one from the fee config of its factory,
+        // and never more than the instantiator accepted
+        fn fee_bps(&self) -> u16 {
+            self.fee_config.map_or(0, |fee_config| fee_config.get_fee_bps().min(self.max_fee_bps))
+        }
+
+        // The protocol fee goes to the recipient of the fee config, which cannot block fills by refusing it,
+        // and the rest of the payment to the escrow
+        fn exchange_entry(&mut self, payment: Vec<Bucket>, taker: TakerInput, fee_bps: u16) -> (Vec<Bucket>, Vec<Bucket>) {
+            let (offered, change, fees) = self.entry.exchange(payment, fee_bps, taker);
+            if let Some(fee_config) = self.fee_config.filter(|_| fee_bps > 0) {
+                let mut fee_recipient: Global<Account> = fee_config.get_fee_recipient().into();
+                for fee in fees {
+                    if let Some(refused) = fee_recipient.try_deposit_or_refund(fee, None) {
+                        let resource_address = refused.resource_address();
+                        if self.fee_vaults.get(&resource_address).is_none() {
+                            self.fee_vaults.insert(resource_address, Vault::new(resource_address));
+                        }
+                        self.fee_vaults.get_mut(&resource_address).unwrap().put(refused);
+                    }
+                }
+            }
+            (offered, change)
+        }
+
+        // Access rule met by the EscrowBadge with the given id
+        fn maker_rule(escrow_nft: ResourceAddress, escrow_nft_id: NonFungibleLocalId) -> AccessRule {
+            rule!(require(NonFungibleGlobalId::new(escrow_nft, escrow_nft_id)))
+        }
+
+        // The component itself keeps a say in its ownership, so it can move it over to a reissued badge
+        fn owner_rule(
+            escrow_nft: ResourceAddress,
+            escrow_nft_id: NonFungibleLocalId,
+            component_address: ComponentAddress
+        ) -> AccessRule {
+            rule!(require(NonFungibleGlobalId::new(escrow_nft, escrow_nft_id)) || require(global_caller(component_address)))
+        }
+
+        // Keeping the badge in sync with the terms it manages
+        fn sync_badge(&self) {
+            let escrow_nft = ResourceManager::from(self.escrow_nft);
+            escrow_nft.update_non_fungible_data(
+                &self.entry.escrow_nft_id,
+                "requested_resources",
+                self.entry.requested_resources.clone(),
+            );
+            escrow_nft.update_non_fungible_data(&self.entry.escrow_nft_id, "expiry_epoch", self.entry.expiry_epoch());
+        }
+
+        // Method to verify the provided NFT is the correct EscrowBadge
+        fn verify_escrow_badge(&self, escrow_nft: NonFungibleProof) {
+            let escrow_nft = escrow_nft.check_with_message(self.escrow_nft, "Invalid Escrow NFT");
+            assert!(escrow_nft.contains_non_fungible(&self.entry.escrow_nft_id), "Invalid Escrow NFT");
+        }
+
+        // Method to verify the provided NFT is the badge minted to the taker of this escrow
+        // Works out whether the instantiator or the taker presented the proof
+        fn verify_party(&self, party: NonFungibleProof) -> SettlementParty {
+            if party.resource_address() == self.escrow_nft {
+                self.verify_escrow_badge(party);
+                SettlementParty::Maker
+            } else {
+                self.verify_taker_badge(party);
+                SettlementParty::Taker
+            }
+        }
+
+        fn verify_taker_badge(&self, taker_nft: NonFungibleProof) {
+            let taker_nft = taker_nft.check_with_message(self.entry.taker_badge, "Invalid Taker NFT");
+            assert!(
+                self.entry.taker_nft_id.as_ref().is_some_and(|taker_nft_id| taker_nft.contains_non_fungible(taker_nft_id)),
+                "Invalid Taker NFT"
+            );
+        }
+    }
+}
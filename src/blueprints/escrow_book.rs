@@ -0,0 +1,628 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::settlement::MAX_FEE_BPS;
+use crate::types::*;
+// Our own specifier, not the one re-exported by the scrypto prelude
+use crate::types::ResourceSpecifier;
+
+#[blueprint]
+#[events(
+    EscrowInstantiated, EscrowFilled, EscrowCancelled, EscrowWithdrawn,
+    CounterOfferProposed, CounterOfferAccepted, CounterOfferRejected,
+    DisputeRaised, DisputeVoteCast, DisputeResolved, SecretRevealed, UnsoldWithdrawn,
+    OfferedAdded, RequestedUpdated, AlternativeChosen, EscrowOwnershipTransferred, BeneficiaryPaid,
+    ExpiredReclaimed, ReferralFeeAccrued, CancellationPenaltyPaid,
+    TakerLegDeposited, TakerLegWithdrawn, ApprovalRequested, FillApproved, UnapprovedRefunded,
+    SettlementClaimed, BondPosted, BondSlashed, BondWithdrawn, PremiumPaid, CoverageUnderwritten, CoveragePaidOut,
+    PaymentConverted, TakerAllowlistUpdated, EscrowSoldOut, EscrowClosed,
+    DeadlineExtended, ProceedsPaidOut, CreatorRoyaltyAccrued
+)]
+mod escrow_book {
+    use super::ResourceSpecifier;
+
+    // Holds many escrows in a single component, each one keyed by the local ID of its EscrowBadge
+    struct EscrowBook {
+        escrows: KeyValueStore<NonFungibleLocalId, EscrowEntry>,
+        escrow_nft: ResourceManager,
+        taker_badge: ResourceManager,
+        // Receipts are shared by all escrows of the book, one resource for each kind
+        transferable_receipt: ResourceManager,
+        soulbound_receipt: ResourceManager,
+        reputation_attestation: ResourceManager,
+        settlement_claim: ResourceManager,
+        fee_bps: u16,
+        fee_vaults: KeyValueStore<ResourceAddress, Vault>,
+        fee_admin_badge: ResourceAddress,
+        // Takers presenting this badge fill at zero fee, e.g. the members of a market maker program
+        fee_exemption_badge: Option<ResourceAddress>,
+        fee_exemptions_used: u64,
+        // Number of EscrowBadges minted so far, the next one is numbered after it
+        badge_count: u64,
+    }
+
+    impl EscrowBook {
+
+        // The fee rate is in basis points of every fungible payment, zero turns fees off
+        pub fn instantiate_escrow_book(fee_bps: u16) -> (Global<EscrowBook>, FungibleBucket) {
+            assert!(fee_bps <= MAX_FEE_BPS, "Fee rate cannot exceed 100%");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(EscrowBook::blueprint_id());
+
+            let escrow_nft = escrow_badge_builder(component_address).create_with_no_initial_supply();
+            let taker_badge = taker_badge_builder(component_address).create_with_no_initial_supply();
+            let transferable_receipt = taker_receipt_builder(component_address, TakerReceipts::Transferable)
+                .create_with_no_initial_supply();
+            let soulbound_receipt = taker_receipt_builder(component_address, TakerReceipts::Soulbound)
+                .create_with_no_initial_supply();
+            let reputation_attestation = reputation_attestation_builder(component_address).create_with_no_initial_supply();
+            let settlement_claim = settlement_claim_builder(component_address).create_with_no_initial_supply();
+
+            // Minting the badge that allows the operator of the book to withdraw the collected fees
+            let fee_admin_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata(metadata! {
+                    init {
+                        "name" => "Scrypto 101 Escrow Fee Admin Badge", locked;
+                    }
+                })
+                .mint_initial_supply(1);
+
+            let component = Self {
+                escrows: KeyValueStore::new(),
+                escrow_nft,
+                taker_badge,
+                transferable_receipt,
+                soulbound_receipt,
+                reputation_attestation,
+                settlement_claim,
+                fee_bps,
+                fee_vaults: KeyValueStore::new(),
+                fee_admin_badge: fee_admin_badge.resource_address(),
+                fee_exemption_badge: None,
+                fee_exemptions_used: 0,
+                badge_count: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, fee_admin_badge)
+        }
+
+        // Opens a new escrow in this book and returns the badge that manages it
+        pub fn create_escrow(
+            &mut self,
+            requested_resources: Vec<ResourceSpecifier>,
+            offered_resources: Vec<Bucket>,
+            config: EscrowConfig
+        ) -> NonFungibleBucket {
+            // The badges of a book share one resource, so a recall could not be limited to a single escrow
+            assert!(config.badge_recovery.is_none(), "Badge recovery is only supported by standalone escrows");
+            assert!(config.badge_id_kind == BadgeIdKind::Integer, "Badges of a book are numbered by the book");
+            assert!(config.soulbound_badge.is_none(), "Soulbound badges are only supported by standalone escrows");
+            // The book charges its own fee
+            assert!(config.fee_config.is_none(), "Escrows of a book cannot read a fee config");
+
+            self.badge_count += 1;
+            let badge = self.escrow_nft.mint_non_fungible(&NonFungibleLocalId::integer(self.badge_count), EscrowBadge {
+                requested_resources: requested_resources.clone(),
+                offered_resources: amounts_of(&offered_resources),
+                created_epoch: Runtime::current_epoch().number(),
+                expiry_epoch: config.expiry_epoch,
+                currency_prices: config.currency_prices.clone(),
+                escrow_component: Runtime::global_address(),
+            }).as_non_fungible();
+            let escrow_nft_id = badge.non_fungible_local_id();
+
+            let resources = EscrowResources {
+                taker_badge: self.taker_badge.address(),
+                taker_receipt: config.taker_receipts.map(|taker_receipts| match taker_receipts {
+                    TakerReceipts::Transferable => self.transferable_receipt.address(),
+                    TakerReceipts::Soulbound => self.soulbound_receipt.address(),
+                }),
+                reputation_attestation: config.reputation_attestations.then(|| self.reputation_attestation.address()),
+                settlement_claim: config.claim_settlement.then(|| self.settlement_claim.address()),
+            };
+            let entry = EscrowEntry::new(
+                escrow_nft_id.clone(),
+                requested_resources,
+                offered_resources,
+                config,
+                resources,
+            );
+            self.escrows.insert(escrow_nft_id, entry);
+
+            badge
+        }
+
+        // Opens one escrow per (requested, offered) pair with the same settings,
+        // returning the badges of all of them in a single bucket
+        pub fn create_escrows(
+            &mut self,
+            listings: Vec<(ResourceSpecifier, Bucket)>,
+            config: EscrowConfig
+        ) -> NonFungibleBucket {
+            assert!(!listings.is_empty(), "At least one escrow is required");
+
+            let mut badges = Bucket::new(self.escrow_nft.address());
+            for (requested_resource, offered_resource) in listings {
+                let badge = self.create_escrow(vec![requested_resource], vec![offered_resource], config.clone());
+                badges.put(badge.into());
+            }
+
+            badges.as_non_fungible()
+        }
+
+        pub fn exchange(&mut self, escrow_nft_id: NonFungibleLocalId, payment: Vec<Bucket>) -> (Vec<Bucket>, Vec<Bucket>) {
+            self.exchange_entry(&escrow_nft_id, payment, TakerInput::default(), self.fee_bps)
+        }
+
+        pub fn exchange_with_secret(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            secret: Vec<u8>
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { secret: Some(secret), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_with_terms(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            terms_hash: Hash
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { terms_hash: Some(terms_hash), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_with_referrer(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            referrer: ResourceAddress
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { referrer: Some(referrer), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_with_credential(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            credential: NonFungibleProof
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { credential: Some(credential), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_as(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            account: ComponentAddress
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { account: Some(account), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_with_max_price(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            max_price: Decimal
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let taker = TakerInput { max_price: Some(max_price), ..Default::default() };
+            self.exchange_entry(&escrow_nft_id, payment, taker, self.fee_bps)
+        }
+
+        pub fn exchange_fee_exempt(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            exemption: Proof
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let fee_exemption_badge = self.fee_exemption_badge.expect("Book has no fee exemption badge");
+            exemption.check_with_message(fee_exemption_badge, "Invalid fee exemption badge");
+            self.fee_exemptions_used += 1;
+
+            self.exchange_entry(&escrow_nft_id, payment, TakerInput::default(), 0)
+        }
+
+        // Referrers claim per escrow, with a proof of the resource that identifies them
+        pub fn claim_referral_fees(&mut self, escrow_nft_id: NonFungibleLocalId, referrer: Proof) -> Vec<Bucket> {
+            self.entry_mut(&escrow_nft_id).claim_referral_fees(referrer.resource_address())
+        }
+
+        pub fn claim_payout(&mut self, escrow_nft_id: NonFungibleLocalId, recipient: Proof) -> Vec<Bucket> {
+            self.entry_mut(&escrow_nft_id).claim_payout(PayoutRecipient::Badge(recipient.resource_address()))
+        }
+
+        pub fn deliver_payout(&mut self, escrow_nft_id: NonFungibleLocalId, account: ComponentAddress) {
+            let payout = self.entry_mut(&escrow_nft_id).claim_payout(PayoutRecipient::Account(account));
+            let mut account: Global<Account> = account.into();
+            account.try_deposit_batch_or_abort(payout, None);
+        }
+
+        pub fn claim_creator_royalties(&mut self, escrow_nft_id: NonFungibleLocalId, creator: Proof) -> Vec<Bucket> {
+            self.entry_mut(&escrow_nft_id).claim_creator_royalties(creator.resource_address())
+        }
+
+        pub fn observe_price(&mut self, escrow_nft_id: NonFungibleLocalId) {
+            self.entry_mut(&escrow_nft_id).observe_price();
+        }
+
+        pub fn refund_expired(&mut self, escrow_nft_id: NonFungibleLocalId) {
+            self.entry_mut(&escrow_nft_id).refund_expired();
+        }
+
+        pub fn pay_beneficiary(&mut self, escrow_nft_id: NonFungibleLocalId) {
+            self.entry_mut(&escrow_nft_id).pay_beneficiary();
+        }
+
+        pub fn reclaim_expired(&mut self, escrow_nft_id: NonFungibleLocalId) -> Vec<Bucket> {
+            self.entry_mut(&escrow_nft_id).reclaim_expired()
+        }
+
+        pub fn deposit_taker_leg(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            taker_account: ComponentAddress
+        ) -> (Bucket, Vec<Bucket>) {
+            self.entry_mut(&escrow_nft_id).deposit_taker_leg(payment, taker_account)
+        }
+
+        pub fn withdraw_taker_leg(&mut self, taker_nft: NonFungibleProof) -> Vec<Bucket> {
+            let escrow_nft_id = self.verify_taker_badge(taker_nft);
+            self.entry_mut(&escrow_nft_id).withdraw_taker_leg()
+        }
+
+        pub fn settle(&mut self, escrow_nft_id: NonFungibleLocalId) {
+            self.entry_mut(&escrow_nft_id).settle();
+        }
+
+        pub fn request_approval(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            taker_account: ComponentAddress
+        ) -> (Bucket, Vec<Bucket>) {
+            self.entry_mut(&escrow_nft_id).request_approval(payment, taker_account)
+        }
+
+        pub fn approve(&mut self, escrow_nft: NonFungibleProof, taker_claim: NonFungibleLocalId) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).approve(taker_claim);
+        }
+
+        pub fn refund_unapproved(&mut self, escrow_nft_id: NonFungibleLocalId) {
+            self.entry_mut(&escrow_nft_id).refund_unapproved();
+        }
+
+        // Claims are minted per escrow, so the claim data tells which escrow owes what
+        pub fn claim(&mut self, claim_nft: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(claim_nft.resource_address() == self.settlement_claim.address(), "Invalid settlement claim");
+            let escrow_nft_id = claim_nft.non_fungible::<SettlementClaim>().data().escrow_nft_id;
+            self.entry_mut(&escrow_nft_id).claim(claim_nft)
+        }
+
+        pub fn withdraw_resource(&mut self, escrow_nft: NonFungibleProof) -> Vec<Bucket> {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).withdraw()
+        }
+
+        pub fn update_requested(&mut self, escrow_nft: NonFungibleProof, requested_resources: Vec<ResourceSpecifier>) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).update_requested(requested_resources);
+            self.sync_badge(&escrow_nft_id);
+        }
+
+        pub fn extend_deadline(&mut self, escrow_nft: NonFungibleProof, deadline: Deadline) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).extend_deadline(deadline);
+            self.sync_badge(&escrow_nft_id);
+        }
+
+        pub fn add_offered(&mut self, escrow_nft: NonFungibleProof, bucket: Bucket) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).add_offered(bucket);
+        }
+
+        pub fn add_allowed_taker(&mut self, escrow_nft: NonFungibleProof, taker: ComponentAddress) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).set_taker_allowed(taker, true);
+        }
+
+        pub fn remove_allowed_taker(&mut self, escrow_nft: NonFungibleProof, taker: ComponentAddress) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).set_taker_allowed(taker, false);
+        }
+
+        pub fn withdraw_unsold(&mut self, escrow_nft: NonFungibleProof, retired_amount: Decimal) -> Vec<Bucket> {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            let returned = self.entry_mut(&escrow_nft_id).withdraw_unsold(retired_amount);
+            self.sync_badge(&escrow_nft_id);
+            returned
+        }
+
+        pub fn cancel_escrow(&mut self, escrow_nft: NonFungibleProof) -> Vec<Bucket> {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).cancel()
+        }
+
+        // Winds an escrow up for good in exchange for its burnt badge. The entry stays behind for its history.
+        pub fn close(&mut self, escrow_nft: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                escrow_nft.resource_address() == self.escrow_nft.address() && escrow_nft.amount() == Decimal::ONE,
+                "Invalid Escrow NFT"
+            );
+            let escrow_nft_id = escrow_nft.non_fungible_local_id();
+            escrow_nft.burn();
+            self.entry_mut(&escrow_nft_id).close()
+        }
+
+        // Hands an escrow over to a new manager, re-keying it under a freshly minted badge
+        pub fn transfer_ownership(&mut self, escrow_nft: NonFungibleBucket) -> NonFungibleBucket {
+            assert!(
+                escrow_nft.resource_address() == self.escrow_nft.address() && escrow_nft.amount() == Decimal::ONE,
+                "Invalid Escrow NFT"
+            );
+            let previous_escrow_nft_id = escrow_nft.non_fungible_local_id();
+            let mut entry = self.escrows.remove(&previous_escrow_nft_id).expect("Escrow not found");
+
+            let badge_data = escrow_nft.non_fungible::<EscrowBadge>().data();
+            escrow_nft.burn();
+            self.badge_count += 1;
+            let badge = self.escrow_nft
+                .mint_non_fungible(&NonFungibleLocalId::integer(self.badge_count), badge_data)
+                .as_non_fungible();
+            let escrow_nft_id = badge.non_fungible_local_id();
+            entry.reissue_badge(escrow_nft_id.clone());
+
+            // Taker badges look their escrow up by badge, so they follow it to the new key
+            if let Some(taker_nft_id) = &entry.taker_nft_id {
+                self.taker_badge.update_non_fungible_data(taker_nft_id, "escrow_nft_id", escrow_nft_id.clone());
+            }
+            self.escrows.insert(escrow_nft_id, entry);
+
+            badge
+        }
+
+        pub fn propose_counter_offer(
+            &mut self,
+            escrow_nft_id: NonFungibleLocalId,
+            requested_resources: Vec<ResourceSpecifier>,
+            proposer: Option<ComponentAddress>
+        ) -> u64 {
+            self.entry_mut(&escrow_nft_id).propose_counter_offer(requested_resources, proposer)
+        }
+
+        pub fn accept_counter_offer(&mut self, escrow_nft: NonFungibleProof, counter_offer_id: u64) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).accept_counter_offer(counter_offer_id);
+            self.sync_badge(&escrow_nft_id);
+        }
+
+        pub fn reject_counter_offer(&mut self, escrow_nft: NonFungibleProof, counter_offer_id: u64) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).reject_counter_offer(counter_offer_id);
+        }
+
+        pub fn get_counter_offer(&self, escrow_nft_id: NonFungibleLocalId, counter_offer_id: u64) -> Option<CounterOffer> {
+            self.entry(&escrow_nft_id).counter_offer(counter_offer_id)
+        }
+
+        pub fn raise_dispute(&mut self, party: NonFungibleProof) {
+            let (escrow_nft_id, _party) = self.verify_party(party);
+            self.entry_mut(&escrow_nft_id).raise_dispute();
+        }
+
+        pub fn resolve_dispute(&mut self, escrow_nft_id: NonFungibleLocalId, arbiter: Proof, award: DisputeAward) {
+            self.entry_mut(&escrow_nft_id).resolve_dispute(arbiter, award);
+        }
+
+        pub fn claim_refund(&mut self, taker_nft: NonFungibleProof) -> Vec<Bucket> {
+            let escrow_nft_id = self.verify_taker_badge(taker_nft);
+            self.entry_mut(&escrow_nft_id).claim_refund()
+        }
+
+        pub fn post_maker_bond(&mut self, escrow_nft: NonFungibleProof, bond: Bucket) {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).post_bond(SettlementParty::Maker, bond);
+        }
+
+        pub fn post_taker_bond(&mut self, taker_nft: NonFungibleProof, bond: Bucket) {
+            let escrow_nft_id = self.verify_taker_badge(taker_nft);
+            self.entry_mut(&escrow_nft_id).post_bond(SettlementParty::Taker, bond);
+        }
+
+        pub fn withdraw_maker_bond(&mut self, escrow_nft: NonFungibleProof) -> Bucket {
+            let escrow_nft_id = self.verify_escrow_badge(escrow_nft);
+            self.entry_mut(&escrow_nft_id).withdraw_bond(SettlementParty::Maker)
+        }
+
+        pub fn withdraw_taker_bond(&mut self, taker_nft: NonFungibleProof) -> Bucket {
+            let escrow_nft_id = self.verify_taker_badge(taker_nft);
+            self.entry_mut(&escrow_nft_id).withdraw_bond(SettlementParty::Taker)
+        }
+
+        pub fn claim_arbiter_bond_cut(&mut self, escrow_nft_id: NonFungibleLocalId, arbiter: Proof) -> Bucket {
+            self.entry_mut(&escrow_nft_id).claim_arbiter_bond_cut(arbiter)
+        }
+
+        pub fn pay_premium(&mut self, party: NonFungibleProof, premium: Bucket) {
+            let (escrow_nft_id, party) = self.verify_party(party);
+            self.entry_mut(&escrow_nft_id).pay_premium(party, premium);
+        }
+
+        pub fn withdraw_premium(&mut self, party: NonFungibleProof) -> Bucket {
+            let (escrow_nft_id, party) = self.verify_party(party);
+            self.entry_mut(&escrow_nft_id).withdraw_premium(party)
+        }
+
+        pub fn underwrite(&mut self, escrow_nft_id: NonFungibleLocalId, insurer: Proof, coverage: Bucket) -> Bucket {
+            self.entry_mut(&escrow_nft_id).underwrite(insurer, coverage)
+        }
+
+        pub fn claim_coverage(&mut self, party: NonFungibleProof) -> Bucket {
+            let (escrow_nft_id, party) = self.verify_party(party);
+            self.entry_mut(&escrow_nft_id).claim_coverage(party)
+        }
+
+        pub fn withdraw_coverage(&mut self, escrow_nft_id: NonFungibleLocalId, insurer: Proof) -> Bucket {
+            self.entry_mut(&escrow_nft_id).withdraw_coverage(insurer)
+        }
+
+        pub fn get_details(&self, escrow_nft_id: NonFungibleLocalId) -> EscrowDetails {
+            self.entry(&escrow_nft_id).details(self.escrow_nft.address())
+        }
+
+        pub fn get_status(&self, escrow_nft_id: NonFungibleLocalId) -> EscrowStatus {
+            self.entry(&escrow_nft_id).status()
+        }
+
+        pub fn get_remaining_amount(&self, escrow_nft_id: NonFungibleLocalId, resource_address: ResourceAddress) -> Decimal {
+            self.entry(&escrow_nft_id).remaining_amount(resource_address)
+        }
+
+        pub fn get_required_payment(
+            &self,
+            escrow_nft_id: NonFungibleLocalId,
+            resource_address: ResourceAddress,
+            amount: Decimal
+        ) -> RequiredPayment {
+            self.entry(&escrow_nft_id).required_payment(resource_address, amount, self.fee_bps)
+        }
+
+        pub fn preview_exchange(
+            &self,
+            escrow_nft_id: NonFungibleLocalId,
+            resource_address: ResourceAddress,
+            amount: Decimal
+        ) -> ExchangePreview {
+            self.entry(&escrow_nft_id).preview_exchange(resource_address, amount, self.fee_bps)
+        }
+
+        pub fn get_current_price(&self, escrow_nft_id: NonFungibleLocalId) -> Option<Decimal> {
+            self.entry(&escrow_nft_id).current_price()
+        }
+
+        pub fn get_unit_price(&self, escrow_nft_id: NonFungibleLocalId) -> Decimal {
+            self.entry(&escrow_nft_id).unit_price()
+        }
+
+        pub fn get_current_tranche(&self, escrow_nft_id: NonFungibleLocalId) -> u32 {
+            self.entry(&escrow_nft_id).current_tranche()
+        }
+
+        pub fn get_taker_filled_amount(&self, escrow_nft_id: NonFungibleLocalId, taker_id: TakerId) -> Decimal {
+            self.entry(&escrow_nft_id).taker_filled_amount(&taker_id)
+        }
+
+        pub fn is_price_fresh(&self, escrow_nft_id: NonFungibleLocalId) -> bool {
+            self.entry(&escrow_nft_id).is_price_fresh()
+        }
+
+        // Method allows the operator of the book to withdraw the fees collected in one resource
+        pub fn withdraw_fees(&mut self, fee_admin: Proof, resource_address: ResourceAddress) -> Bucket {
+            fee_admin.check_with_message(self.fee_admin_badge, "Invalid fee admin badge");
+
+            self.fee_vaults
+                .get_mut(&resource_address)
+                .expect("No fees collected in this resource")
+                .take_all()
+        }
+
+        // Method allows the operator of the book to set or clear the badge that exempts takers from the fee
+        pub fn set_fee_exemption_badge(&mut self, fee_admin: Proof, fee_exemption_badge: Option<ResourceAddress>) {
+            fee_admin.check_with_message(self.fee_admin_badge, "Invalid fee admin badge");
+            self.fee_exemption_badge = fee_exemption_badge;
+        }
+
+        pub fn get_fee_exemptions_used(&self) -> u64 {
+            self.fee_exemptions_used
+        }
+
+        pub fn get_fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        pub fn get_collected_fees(&self, resource_address: ResourceAddress) -> Decimal {
+            self.fee_vaults
+                .get(&resource_address)
+                .map(|vault| vault.amount())
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        // The protocol fee is kept by the book, the rest of the payment goes to the escrow
+        fn exchange_entry(
+            &mut self,
+            escrow_nft_id: &NonFungibleLocalId,
+            payment: Vec<Bucket>,
+            taker: TakerInput,
+            fee_bps: u16
+        ) -> (Vec<Bucket>, Vec<Bucket>) {
+            let (offered, change, fees) = self.entry_mut(escrow_nft_id).exchange(payment, fee_bps, taker);
+
+            for fee in fees {
+                let resource_address = fee.resource_address();
+                if self.fee_vaults.get(&resource_address).is_none() {
+                    self.fee_vaults.insert(resource_address, Vault::new(resource_address));
+                }
+                self.fee_vaults.get_mut(&resource_address).unwrap().put(fee);
+            }
+
+            (offered, change)
+        }
+
+        // Keeping the badge in sync with the terms it manages
+        fn sync_badge(&self, escrow_nft_id: &NonFungibleLocalId) {
+            let (requested_resources, expiry_epoch) = {
+                let entry = self.entry(escrow_nft_id);
+                (entry.requested_resources.clone(), entry.expiry_epoch())
+            };
+            self.escrow_nft.update_non_fungible_data(escrow_nft_id, "requested_resources", requested_resources);
+            self.escrow_nft.update_non_fungible_data(escrow_nft_id, "expiry_epoch", expiry_epoch);
+        }
+
+        fn entry(&self, escrow_nft_id: &NonFungibleLocalId) -> KeyValueEntryRef<'_, EscrowEntry> {
+            self.escrows.get(escrow_nft_id).expect("Escrow not found")
+        }
+
+        fn entry_mut(&mut self, escrow_nft_id: &NonFungibleLocalId) -> KeyValueEntryRefMut<'_, EscrowEntry> {
+            self.escrows.get_mut(escrow_nft_id).expect("Escrow not found")
+        }
+
+        // Method to verify the provided NFT is an EscrowBadge of this book, returning the escrow it manages
+        fn verify_escrow_badge(&self, escrow_nft: NonFungibleProof) -> NonFungibleLocalId {
+            escrow_nft
+                .check_with_message(self.escrow_nft.address(), "Invalid Escrow NFT")
+                .non_fungible_local_id()
+        }
+
+        // Method to verify the provided NFT is the badge minted to the taker of an escrow, returning that escrow
+        // Works out the escrow and whether its instantiator or its taker presented the proof
+        fn verify_party(&self, party: NonFungibleProof) -> (NonFungibleLocalId, SettlementParty) {
+            if party.resource_address() == self.escrow_nft.address() {
+                (self.verify_escrow_badge(party), SettlementParty::Maker)
+            } else {
+                (self.verify_taker_badge(party), SettlementParty::Taker)
+            }
+        }
+
+        fn verify_taker_badge(&self, taker_nft: NonFungibleProof) -> NonFungibleLocalId {
+            let taker_nft = taker_nft.check_with_message(self.taker_badge.address(), "Invalid Taker NFT");
+            let escrow_nft_id = taker_nft.non_fungible::<TakerBadge>().data().escrow_nft_id;
+            assert!(
+                self.entry(&escrow_nft_id).taker_nft_id == Some(taker_nft.non_fungible_local_id()),
+                "Invalid Taker NFT"
+            );
+            escrow_nft_id
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use scrypto::prelude::*;
+
+use crate::types::*;
+// Our own specifier, not the one re-exported by the scrypto prelude
+use crate::types::ResourceSpecifier;
+use crate::{Escrow, EscrowFunctions};
+
+#[blueprint]
+mod escrow_factory {
+    use super::ResourceSpecifier;
+
+    struct EscrowFactory {
+        // Fee settings every escrow of the factory reads at fill time
+        fee_config: Option<ComponentAddress>,
+        escrows: KeyValueStore<ComponentAddress, EscrowListing>,
+        escrow_index: KeyValueStore<u64, ComponentAddress>,
+        escrow_count: u64,
+        // Escrows that can still be filled, kept dense by moving the last one into the slot of a delisted one
+        open_index: KeyValueStore<u64, ComponentAddress>,
+        open_positions: KeyValueStore<ComponentAddress, u64>,
+        open_count: u64,
+    }
+
+    impl EscrowFactory {
+
+        pub fn instantiate_factory(fee_config: Option<ComponentAddress>) -> Global<EscrowFactory> {
+            Self {
+                fee_config,
+                escrows: KeyValueStore::new(),
+                escrow_index: KeyValueStore::new(),
+                escrow_count: 0,
+                open_index: KeyValueStore::new(),
+                open_positions: KeyValueStore::new(),
+                open_count: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        // Instantiates a new escrow and records it in the registry
+        pub fn create_escrow(
+            &mut self,
+            requested_resources: Vec<ResourceSpecifier>,
+            offered_resources: Vec<Bucket>,
+            mut config: EscrowConfig,
+            exchange_royalty: RoyaltyAmount
+        ) -> (Global<Escrow>, NonFungibleBucket) {
+            config.fee_config = self.fee_config;
+            let (escrow, badge) = Blueprint::<Escrow>::instantiate_escrow(
+                requested_resources,
+                offered_resources,
+                config,
+                exchange_royalty,
+            );
+
+            let escrow_address = escrow.address();
+            let details = escrow.get_details();
+            self.escrows.insert(escrow_address, EscrowListing {
+                escrow_component: escrow_address,
+                escrow_nft: details.escrow_nft,
+                requested_resources: details.requested_resources,
+                offered_resources: details.offered_resources,
+                created_epoch: Runtime::current_epoch().number(),
+            });
+            self.escrow_index.insert(self.escrow_count, escrow_address);
+            self.escrow_count += 1;
+            self.open_index.insert(self.open_count, escrow_address);
+            self.open_positions.insert(escrow_address, self.open_count);
+            self.open_count += 1;
+
+            (escrow, badge)
+        }
+
+        pub fn get_escrow(&self, escrow_address: ComponentAddress) -> Option<EscrowListing> {
+            self.escrows.get(&escrow_address).map(|listing| listing.clone())
+        }
+
+        pub fn get_escrow_count(&self) -> u64 {
+            self.escrow_count
+        }
+
+        pub fn get_open_escrow_count(&self) -> u64 {
+            self.open_count
+        }
+
+        // Lists the registered escrows in creation order, one page at a time
+        pub fn list_escrows(&self, start: u64, limit: u64) -> Vec<EscrowListing> {
+            (start..self.escrow_count.min(start.saturating_add(limit)))
+                .filter_map(|index| self.listing_at(index))
+                .collect()
+        }
+
+        // Lists the escrows of the open listings one page at a time. Escrows that ended since they were last
+        // synced are left out, while they wait for `sync_status` to delist them.
+        pub fn list_open_escrows(&self, start: u64, limit: u64) -> Vec<EscrowListing> {
+            (start..self.open_count.min(start.saturating_add(limit)))
+                .filter_map(|index| {
+                    let escrow_address = *self.open_index.get(&index)?;
+                    self.get_escrow(escrow_address)
+                })
+                .filter(|listing| {
+                    let escrow: Global<Escrow> = listing.escrow_component.into();
+                    !escrow.is_closed()
+                })
+                .collect()
+        }
+
+        // Method removes an escrow that was filled, cancelled, sold out or has expired from the open listings,
+        // anyone can trigger it. Returns whether the escrow was delisted.
+        pub fn sync_status(&mut self, escrow_address: ComponentAddress) -> bool {
+            let Some(position) = self.open_positions.get(&escrow_address).map(|position| *position) else {
+                return false;
+            };
+            // Only states an escrow never returns from, an expired deadline cannot be extended any more
+            let escrow: Global<Escrow> = escrow_address.into();
+            let ended = matches!(
+                escrow.get_status(),
+                EscrowStatus::Filled
+                    | EscrowStatus::Cancelled
+                    | EscrowStatus::Withdrawn
+                    | EscrowStatus::Expired
+                    | EscrowStatus::Refunded
+                    | EscrowStatus::SoldOut
+                    | EscrowStatus::Closed
+            );
+            if !ended {
+                return false;
+            }
+
+            // The last open escrow takes the slot of the delisted one
+            self.open_count -= 1;
+            let last_address = *self.open_index.get(&self.open_count).unwrap();
+            if position != self.open_count {
+                self.open_index.insert(position, last_address);
+                self.open_positions.insert(last_address, position);
+            }
+            self.open_index.remove(&self.open_count);
+            self.open_positions.remove(&escrow_address);
+            true
+        }
+
+        fn listing_at(&self, index: u64) -> Option<EscrowListing> {
+            let escrow_address = *self.escrow_index.get(&index)?;
+            self.get_escrow(escrow_address)
+        }
+    }
+}
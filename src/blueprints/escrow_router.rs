@@ -0,0 +1,47 @@
+use scrypto::prelude::*;
+
+use crate::Escrow;
+
+#[blueprint]
+mod escrow_router {
+
+    struct EscrowRouter {}
+
+    impl EscrowRouter {
+
+        // Fills the given escrows in sequence with one payment until it runs out.
+        // Escrows that cannot be filled with what is left of the payment are skipped.
+        // Returns the combined purchases, one bucket per resource, and the rest of the payment.
+        pub fn fill_escrows(mut payment: Bucket, escrows: Vec<Global<Escrow>>) -> (Vec<Bucket>, Bucket) {
+            let payment_resource = payment.resource_address();
+            let mut purchases: IndexMap<ResourceAddress, Bucket> = IndexMap::new();
+
+            for escrow in escrows {
+                if payment.is_empty() {
+                    break;
+                }
+                if escrow.preview_exchange(payment_resource, payment.amount()).failure.is_some() {
+                    continue;
+                }
+
+                let (offered, change) = escrow.exchange(vec![payment]);
+                for bucket in offered {
+                    match purchases.get_mut(&bucket.resource_address()) {
+                        Some(existing) => existing.put(bucket),
+                        None => {
+                            purchases.insert(bucket.resource_address(), bucket);
+                        }
+                    }
+                }
+
+                // The change holds what is left of the payment
+                payment = Bucket::new(payment_resource);
+                for bucket in change {
+                    payment.put(bucket);
+                }
+            }
+
+            (purchases.into_values().collect(), payment)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use scrypto::prelude::*;
+
+use crate::settlement::MAX_FEE_BPS;
+
+#[blueprint]
+mod fee_config {
+
+    // Marketplace fee settings shared by the escrows of a factory. The escrows read them at every fill,
+    // so the operator updates the fee of every live escrow in one place.
+    struct FeeConfig {
+        fee_bps: u16,
+        // Account the fees of every fill are deposited to
+        fee_recipient: ComponentAddress,
+        // Badges whose holders fill at zero fee
+        fee_exemptions: IndexSet<ResourceAddress>,
+        fee_admin_badge: ResourceAddress,
+    }
+
+    impl FeeConfig {
+
+        // The fee rate is in basis points of every fungible payment, zero turns fees off
+        pub fn instantiate_fee_config(fee_bps: u16, fee_recipient: ComponentAddress) -> (Global<FeeConfig>, FungibleBucket) {
+            assert!(fee_bps <= MAX_FEE_BPS, "Fee rate cannot exceed 100%");
+            let _: Global<Account> = fee_recipient.into();
+
+            // Minting the badge that allows the operator to change the fee settings
+            let fee_admin_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata(metadata! {
+                    init {
+                        "name" => "Scrypto 101 Escrow Fee Admin Badge", locked;
+                    }
+                })
+                .mint_initial_supply(1);
+
+            let component = Self {
+                fee_bps,
+                fee_recipient,
+                fee_exemptions: IndexSet::new(),
+                fee_admin_badge: fee_admin_badge.resource_address(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize();
+
+            (component, fee_admin_badge)
+        }
+
+        pub fn set_fee_bps(&mut self, fee_admin: Proof, fee_bps: u16) {
+            fee_admin.check_with_message(self.fee_admin_badge, "Invalid fee admin badge");
+            assert!(fee_bps <= MAX_FEE_BPS, "Fee rate cannot exceed 100%");
+            self.fee_bps = fee_bps;
+        }
+
+        pub fn set_fee_recipient(&mut self, fee_admin: Proof, fee_recipient: ComponentAddress) {
+            fee_admin.check_with_message(self.fee_admin_badge, "Invalid fee admin badge");
+            let _: Global<Account> = fee_recipient.into();
+            self.fee_recipient = fee_recipient;
+        }
+
+        // Method allows the operator to grant or revoke a badge that exempts takers from the fee
+        pub fn set_fee_exemption(&mut self, fee_admin: Proof, fee_exemption_badge: ResourceAddress, exempt: bool) {
+            fee_admin.check_with_message(self.fee_admin_badge, "Invalid fee admin badge");
+            if exempt {
+                self.fee_exemptions.insert(fee_exemption_badge);
+            } else {
+                self.fee_exemptions.shift_remove(&fee_exemption_badge);
+            }
+        }
+
+        pub fn get_fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        pub fn get_fee_recipient(&self) -> ComponentAddress {
+            self.fee_recipient
+        }
+
+        pub fn is_fee_exempt(&self, fee_exemption_badge: ResourceAddress) -> bool {
+            self.fee_exemptions.contains(&fee_exemption_badge)
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use scrypto::prelude::*;
+
+use crate::types::*;
+
+#[blueprint]
+#[events(MilestoneReleased, MilestonesAbandoned)]
+mod milestone_escrow {
+    // Pays a service out in stages. The payer funds every milestone up front and releases them one by one,
+    // while the payee can walk away from the deal, refunding whatever has not been released yet.
+    struct MilestoneEscrow {
+        funds: Vault,
+        milestones: Vec<Milestone>,
+        released: Vault,
+        refunds: Vault,
+        party_badge: ResourceAddress,
+        arbiter_badge: Option<ResourceAddress>,
+        abandoned: bool,
+    }
+
+    impl MilestoneEscrow {
+
+        // Returns the component with the badges of the payer and of the payee, the latter to be handed to the payee.
+        // An arbiter, if any, can release milestones and abandon the deal on behalf of the parties.
+        pub fn instantiate_milestone_escrow(
+            funds: Bucket,
+            milestone_amounts: Vec<Decimal>,
+            arbiter_badge: Option<ResourceAddress>
+        ) -> (Global<MilestoneEscrow>, NonFungibleBucket, NonFungibleBucket) {
+            assert!(funds.resource_address().is_fungible(), "Milestones have to be paid in a fungible resource");
+            assert!(!milestone_amounts.is_empty(), "At least one milestone is required");
+            assert!(
+                milestone_amounts.iter().all(|amount| *amount > Decimal::ZERO),
+                "Milestone amounts must be positive"
+            );
+            assert!(
+                milestone_amounts.iter().fold(Decimal::ZERO, |total, amount| total + *amount) == funds.amount(),
+                "Funds have to match the milestone amounts"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(MilestoneEscrow::blueprint_id());
+
+            let mut payer_badge = ResourceBuilder::new_integer_non_fungible::<PartyBadge>(OwnerRole::None)
+                .metadata(metadata! {
+                    init {
+                        "name" => "Scrypto 101 Milestone Escrow Party Badge", locked;
+                    }
+                })
+                .mint_initial_supply([
+                    (IntegerNonFungibleLocalId::new(1), PartyBadge { party: Party::Payer, escrow_component: component_address }),
+                    (IntegerNonFungibleLocalId::new(2), PartyBadge { party: Party::Payee, escrow_component: component_address }),
+                ]);
+            let payee_badge = payer_badge.take_non_fungible(&NonFungibleLocalId::integer(2));
+
+            let resource_address = funds.resource_address();
+            let component = Self {
+                funds: Vault::with_bucket(funds),
+                milestones: milestone_amounts
+                    .into_iter()
+                    .map(|amount| Milestone { amount, status: MilestoneStatus::Pending })
+                    .collect(),
+                released: Vault::new(resource_address),
+                refunds: Vault::new(resource_address),
+                party_badge: payer_badge.resource_address(),
+                arbiter_badge,
+                abandoned: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, payer_badge, payee_badge)
+        }
+
+        // Method allows the payer or the arbiter to approve a milestone, releasing its amount to the payee
+        pub fn release_milestone(&mut self, party: Proof, milestone_index: u32) {
+            let party = self.party_of(party);
+            assert!(matches!(party, Party::Payer | Party::Arbiter), "Only the payer or the arbiter can release milestones");
+            assert!(!self.abandoned, "Deal has been abandoned");
+
+            let milestone = self.milestones.get_mut(milestone_index as usize).expect("Milestone not found");
+            assert!(milestone.status == MilestoneStatus::Pending, "Milestone is no longer pending");
+            milestone.status = MilestoneStatus::Released;
+            let amount = milestone.amount;
+            self.released.put(self.funds.take(amount));
+
+            Runtime::emit_event(MilestoneReleased {
+                escrow_component: Runtime::global_address(),
+                milestone_index,
+                amount,
+            });
+        }
+
+        // Method allows the payee or the arbiter to walk away from the deal,
+        // refunding every milestone that has not been released to the payer
+        pub fn abandon(&mut self, party: Proof) {
+            let party = self.party_of(party);
+            assert!(matches!(party, Party::Payee | Party::Arbiter), "Only the payee or the arbiter can abandon the deal");
+            assert!(!self.abandoned, "Deal has already been abandoned");
+            self.abandoned = true;
+
+            for milestone in self.milestones.iter_mut().filter(|milestone| milestone.status == MilestoneStatus::Pending) {
+                milestone.status = MilestoneStatus::Refunded;
+            }
+            let refunded = self.funds.amount();
+            self.refunds.put(self.funds.take_all());
+
+            Runtime::emit_event(MilestonesAbandoned {
+                escrow_component: Runtime::global_address(),
+                refunded,
+            });
+        }
+
+        // Method allows the payee to withdraw the released milestones
+        pub fn withdraw_released(&mut self, party: Proof) -> Bucket {
+            assert!(self.party_of(party) == Party::Payee, "Only the payee can withdraw released milestones");
+            self.released.take_all()
+        }
+
+        // Method allows the payer to withdraw the refund of an abandoned deal
+        pub fn withdraw_refund(&mut self, party: Proof) -> Bucket {
+            assert!(self.party_of(party) == Party::Payer, "Only the payer can withdraw the refund");
+            self.refunds.take_all()
+        }
+
+        pub fn get_milestones(&self) -> Vec<Milestone> {
+            self.milestones.clone()
+        }
+
+        // Method to work out which party presented the proof
+        fn party_of(&self, party: Proof) -> Party {
+            let resource_address = party.resource_address();
+            if resource_address == self.party_badge {
+                let party_badge = party.check(self.party_badge);
+                party_badge.as_non_fungible().non_fungible::<PartyBadge>().data().party
+            } else if Some(resource_address) == self.arbiter_badge {
+                party.check(resource_address);
+                Party::Arbiter
+            } else {
+                panic!("Invalid party badge")
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+mod crowdfund_escrow;
+mod english_auction;
+mod escrow;
+mod escrow_book;
+mod escrow_factory;
+mod escrow_router;
+mod fee_config;
+mod milestone_escrow;
+mod raffle_escrow;
+mod sealed_bid_auction;
+mod streaming_escrow;
+
+// Lets the other blueprints of this package call into the Escrow blueprint
+pub use escrow::escrow::{Escrow, EscrowFunctions};
+pub use fee_config::fee_config::FeeConfig;
+
+// Test bindings of every blueprint, as they were exported from the crate root
+pub use crowdfund_escrow::crowdfund_escrow_test;
+pub use english_auction::english_auction_test;
+pub use escrow::escrow_test;
+pub use escrow_book::escrow_book_test;
+pub use escrow_factory::escrow_factory_test;
+pub use escrow_router::escrow_router_test;
+pub use fee_config::fee_config_test;
+pub use milestone_escrow::milestone_escrow_test;
+pub use raffle_escrow::raffle_escrow_test;
+pub use sealed_bid_auction::sealed_bid_auction_test;
+pub use streaming_escrow::streaming_escrow_test;
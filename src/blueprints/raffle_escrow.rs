@@ -0,0 +1,200 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::settlement::*;
+use crate::types::*;
+
+#[blueprint]
+#[events(RaffleTicketBought, RaffleDrawn, RaffleTicketRefunded)]
+mod raffle_escrow {
+    // Raffles the offered resources off among everyone who bought a ticket at the fixed price before the deadline.
+    // The instantiator keeps either every ticket payment, or only the winner's with the other tickets refunded.
+    struct RaffleEscrow {
+        offered_resources: IndexMap<ResourceAddress, Vault>,
+        ticket_resource: ResourceAddress,
+        ticket_price: Decimal,
+        deadline_epoch: u64,
+        refund_losers: bool,
+        randomness: ComponentAddress,
+        ticket_payments: Vault,
+        proceeds: Vault,
+        // Tickets are numbered in the order they were bought, which the draw picks a number from
+        ticket_badge: ResourceManager,
+        ticket_count: u64,
+        winning_ticket: Option<NonFungibleLocalId>,
+        raffle_nft: ResourceAddress,
+        raffle_nft_id: NonFungibleLocalId,
+    }
+
+    impl RaffleEscrow {
+
+        pub fn instantiate_raffle_escrow(
+            offered_resources: Vec<Bucket>,
+            terms: RaffleTerms
+        ) -> (Global<RaffleEscrow>, NonFungibleBucket) {
+            let RaffleTerms { ticket_resource, ticket_price, deadline_epoch, refund_losers, randomness } = terms;
+            assert!(!offered_resources.is_empty(), "At least one offered resource is required");
+            assert!(ticket_resource.is_fungible(), "Tickets have to be paid in a fungible resource");
+            assert!(ticket_price > Decimal::ZERO, "Ticket price must be positive");
+            assert!(deadline_epoch > Runtime::current_epoch().number(), "Deadline must be in the future");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(RaffleEscrow::blueprint_id());
+
+            // The raffle is managed with the same badge as an escrow, asking for the price of one ticket
+            let badge = escrow_badge_builder(component_address)
+                .mint_initial_supply([(1u64.into(), EscrowBadge {
+                    requested_resources: vec![crate::ResourceSpecifier::Fungible {
+                        resource_address: ticket_resource,
+                        amount: ticket_price,
+                    }],
+                    offered_resources: amounts_of(&offered_resources),
+                    created_epoch: Runtime::current_epoch().number(),
+                    expiry_epoch: Some(deadline_epoch),
+                    currency_prices: IndexMap::new(),
+                    escrow_component: component_address,
+                })]);
+            let ticket_badge = ticket_builder::<RaffleTicket>(component_address, "Scrypto 101 Raffle Ticket")
+                .create_with_no_initial_supply();
+
+            let component = Self {
+                offered_resources: vaults_of(offered_resources),
+                ticket_resource,
+                ticket_price,
+                deadline_epoch,
+                refund_losers,
+                randomness,
+                ticket_payments: Vault::new(ticket_resource),
+                proceeds: Vault::new(ticket_resource),
+                ticket_badge,
+                ticket_count: 0,
+                winning_ticket: None,
+                raffle_nft: badge.resource_address(),
+                raffle_nft_id: badge.non_fungible_local_id(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, badge)
+        }
+
+        // Sells one ticket, returning it along with whatever is left of the payment
+        pub fn buy_ticket(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            assert!(Runtime::current_epoch().number() < self.deadline_epoch, "Raffle has ended");
+            assert!(payment.resource_address() == self.ticket_resource, "Invalid payment resource");
+            assert!(payment.amount() >= self.ticket_price, "Insufficient payment");
+            self.ticket_payments.put(payment.take(self.ticket_price));
+
+            self.ticket_count += 1;
+            let ticket_nft_id = NonFungibleLocalId::integer(self.ticket_count);
+            let ticket = self.ticket_badge.mint_non_fungible(
+                &ticket_nft_id,
+                RaffleTicket {
+                    raffle_component: Runtime::global_address(),
+                    purchased_epoch: Runtime::current_epoch().number(),
+                },
+            );
+
+            Runtime::emit_event(RaffleTicketBought {
+                raffle_nft_id: self.raffle_nft_id.clone(),
+                ticket_nft_id,
+            });
+
+            (ticket, payment)
+        }
+
+        // Draws the winning ticket once the raffle has ended, which anyone can trigger
+        pub fn draw(&mut self) {
+            assert!(Runtime::current_epoch().number() >= self.deadline_epoch, "Raffle has not ended yet");
+            assert!(self.winning_ticket.is_none(), "Raffle has already been drawn");
+            assert!(self.ticket_count > 0, "No tickets have been sold");
+
+            let randomness: Global<AnyComponent> = self.randomness.into();
+            let seed: Hash = randomness.call("get_random_seed", &());
+            let winning_ticket = NonFungibleLocalId::integer(draw_index(seed, self.ticket_count) + 1);
+            self.winning_ticket = Some(winning_ticket.clone());
+
+            // The instantiator is paid for the winning ticket only if the others get refunded
+            let proceeds = if self.refund_losers {
+                self.ticket_payments.take(self.ticket_price)
+            } else {
+                self.ticket_payments.take_all()
+            };
+            self.proceeds.put(proceeds);
+
+            Runtime::emit_event(RaffleDrawn {
+                raffle_nft_id: self.raffle_nft_id.clone(),
+                winning_ticket_id: winning_ticket,
+                ticket_count: self.ticket_count,
+            });
+        }
+
+        // Burns the winning ticket for the offered resources
+        pub fn claim_prize(&mut self, ticket: NonFungibleBucket) -> Vec<Bucket> {
+            let ticket_nft_id = self.burn_ticket(ticket);
+            assert!(self.winning_ticket.as_ref() == Some(&ticket_nft_id), "Ticket has not won");
+
+            self.offered_resources.values_mut().map(|vault| vault.take_all()).collect()
+        }
+
+        // Burns a losing ticket for its price, if the raffle refunds losing tickets
+        pub fn refund_ticket(&mut self, ticket: NonFungibleBucket) -> Bucket {
+            assert!(self.refund_losers, "Raffle does not refund losing tickets");
+            assert!(self.winning_ticket.is_some(), "Raffle has not been drawn");
+            let ticket_nft_id = self.burn_ticket(ticket);
+            assert!(self.winning_ticket.as_ref() != Some(&ticket_nft_id), "Winning ticket cannot be refunded");
+
+            Runtime::emit_event(RaffleTicketRefunded {
+                raffle_nft_id: self.raffle_nft_id.clone(),
+                ticket_nft_id,
+                amount: self.ticket_price,
+            });
+
+            self.ticket_payments.take(self.ticket_price)
+        }
+
+        // Method allows the instantiator to withdraw what they earned from the draw
+        pub fn withdraw_proceeds(&mut self, raffle_nft: NonFungibleProof) -> Bucket {
+            self.verify_raffle_badge(raffle_nft);
+            self.proceeds.take_all()
+        }
+
+        // Method allows the instantiator to take the offered resources back when no tickets were sold
+        pub fn reclaim_offered(&mut self, raffle_nft: NonFungibleProof) -> Vec<Bucket> {
+            self.verify_raffle_badge(raffle_nft);
+            assert!(Runtime::current_epoch().number() >= self.deadline_epoch, "Raffle has not ended yet");
+            assert!(self.ticket_count == 0, "Tickets have been sold");
+
+            self.offered_resources.values_mut().map(|vault| vault.take_all()).collect()
+        }
+
+        pub fn get_ticket_count(&self) -> u64 {
+            self.ticket_count
+        }
+
+        pub fn get_winning_ticket(&self) -> Option<NonFungibleLocalId> {
+            self.winning_ticket.clone()
+        }
+
+        pub fn is_drawn(&self) -> bool {
+            self.winning_ticket.is_some()
+        }
+
+        fn burn_ticket(&self, ticket: NonFungibleBucket) -> NonFungibleLocalId {
+            assert!(
+                ticket.resource_address() == self.ticket_badge.address() && ticket.amount() == Decimal::ONE,
+                "Invalid raffle ticket"
+            );
+            let ticket_nft_id = ticket.non_fungible_local_id();
+            ticket.burn();
+            ticket_nft_id
+        }
+
+        fn verify_raffle_badge(&self, raffle_nft: NonFungibleProof) {
+            let raffle_nft = raffle_nft.check_with_message(self.raffle_nft, "Invalid Raffle NFT");
+            assert!(raffle_nft.contains_non_fungible(&self.raffle_nft_id), "Invalid Raffle NFT");
+        }
+    }
+}
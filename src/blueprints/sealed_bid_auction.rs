@@ -0,0 +1,248 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::types::*;
+
+#[blueprint]
+#[events(SealedBidCommitted, SealedBidRevealed, AuctionSettled, ReserveNotMet)]
+mod sealed_bid_auction {
+    // Sells the offered resources to the highest revealed bid.
+    // Bidders commit to a hash of their bid with a bond, and reveal the bid itself once commitments close.
+    struct SealedBidAuction {
+        offered_resources: IndexMap<ResourceAddress, Vault>,
+        bid_resource: ResourceAddress,
+        min_bid: Decimal,
+        bond_amount: Decimal,
+        commit_end_epoch: u64,
+        reveal_end_epoch: u64,
+        reserve_hash: Option<Hash>,
+        sealed_bids: KeyValueStore<u64, SealedBid>,
+        sealed_bid_count: u64,
+        // Highest revealed bid so far, the earliest one on a tie
+        best_bid: Option<(u64, Decimal)>,
+        // Winner once settled, every other bid is withdrawn by its bidder from then on
+        winning_bid_id: Option<u64>,
+        // Bonds of the bids that were never revealed, for the instantiator
+        forfeited_bonds: Vault,
+        unclaimed: KeyValueStore<(ComponentAddress, ResourceAddress), Vault>,
+        auction_nft: ResourceAddress,
+        auction_nft_id: NonFungibleLocalId,
+        settled: bool,
+    }
+
+    impl SealedBidAuction {
+
+        pub fn instantiate_sealed_bid_auction(
+            offered_resources: Vec<Bucket>,
+            terms: SealedBidTerms
+        ) -> (Global<SealedBidAuction>, NonFungibleBucket) {
+            let SealedBidTerms { bid_resource, min_bid, bond_amount, commit_end_epoch, reveal_end_epoch, reserve_hash } = terms;
+            assert!(!offered_resources.is_empty(), "At least one offered resource is required");
+            assert!(bid_resource.is_fungible(), "Bids have to be made in a fungible resource");
+            assert!(min_bid > Decimal::ZERO, "Minimum bid must be positive");
+            assert!(bond_amount > Decimal::ZERO, "Bond amount must be positive");
+            assert!(commit_end_epoch > Runtime::current_epoch().number(), "Commit end epoch must be in the future");
+            assert!(reveal_end_epoch > commit_end_epoch, "Reveal end epoch must be after the commit end epoch");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(SealedBidAuction::blueprint_id());
+
+            let badge = escrow_badge_builder(component_address)
+                .mint_initial_supply([(1u64.into(), EscrowBadge {
+                    requested_resources: vec![crate::ResourceSpecifier::Fungible {
+                        resource_address: bid_resource,
+                        amount: min_bid,
+                    }],
+                    offered_resources: amounts_of(&offered_resources),
+                    created_epoch: Runtime::current_epoch().number(),
+                    expiry_epoch: Some(reveal_end_epoch),
+                    currency_prices: IndexMap::new(),
+                    escrow_component: component_address,
+                })]);
+
+            let component = Self {
+                offered_resources: vaults_of(offered_resources),
+                bid_resource,
+                min_bid,
+                bond_amount,
+                commit_end_epoch,
+                reveal_end_epoch,
+                reserve_hash,
+                sealed_bids: KeyValueStore::new(),
+                sealed_bid_count: 0,
+                best_bid: None,
+                winning_bid_id: None,
+                forfeited_bonds: Vault::new(bid_resource),
+                unclaimed: KeyValueStore::new(),
+                auction_nft: badge.resource_address(),
+                auction_nft_id: badge.non_fungible_local_id(),
+                settled: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, badge)
+        }
+
+        // Computes the commitment of a bid, so bidders can seal it before committing.
+        // The instantiator seals a hidden reserve price the same way.
+        pub fn compute_commitment(bid: Decimal, salt: String) -> Hash {
+            commitment_of(bid, salt)
+        }
+
+        // Seals a bid during the commit window, returning the ID to reveal it with.
+        // The bond is returned on reveal, and forfeited to the instantiator otherwise.
+        pub fn commit_bid(&mut self, commitment: Hash, bond: Bucket, bidder: Global<Account>) -> u64 {
+            assert!(Runtime::current_epoch().number() < self.commit_end_epoch, "Commit window has closed");
+            assert!(bond.resource_address() == self.bid_resource, "Invalid bond resource");
+            assert!(bond.amount() == self.bond_amount, "Bond has to match the bond amount");
+
+            let sealed_bid_id = self.sealed_bid_count;
+            self.sealed_bids.insert(sealed_bid_id, SealedBid {
+                bidder: bidder.address(),
+                commitment,
+                bond: Vault::with_bucket(bond),
+                bid: Vault::new(self.bid_resource),
+                revealed: false,
+            });
+            self.sealed_bid_count += 1;
+
+            Runtime::emit_event(SealedBidCommitted {
+                auction_nft_id: self.auction_nft_id.clone(),
+                sealed_bid_id,
+            });
+
+            sealed_bid_id
+        }
+
+        // Reveals a sealed bid during the reveal window by depositing exactly the committed amount
+        pub fn reveal_bid(&mut self, sealed_bid_id: u64, bid: Bucket, salt: String) {
+            let current_epoch = Runtime::current_epoch().number();
+            assert!(current_epoch >= self.commit_end_epoch, "Reveal window has not opened yet");
+            assert!(current_epoch < self.reveal_end_epoch, "Reveal window has closed");
+            assert!(bid.resource_address() == self.bid_resource, "Invalid bid resource");
+            assert!(bid.amount() >= self.min_bid, "Bid is below the minimum bid");
+
+            let amount = bid.amount();
+            {
+                let mut sealed_bid = self.sealed_bids.get_mut(&sealed_bid_id).expect("Sealed bid not found");
+                assert!(!sealed_bid.revealed, "Bid has already been revealed");
+                assert!(
+                    sealed_bid.commitment == Self::compute_commitment(amount, salt),
+                    "Bid does not match its commitment"
+                );
+                sealed_bid.bid.put(bid);
+                sealed_bid.revealed = true;
+            }
+
+            // Keeping track of the best bid as bids are revealed, so settling does not have to walk them all
+            let outbids = self.best_bid
+                .is_none_or(|(best_id, best)| amount > best || (amount == best && sealed_bid_id < best_id));
+            if outbids {
+                self.best_bid = Some((sealed_bid_id, amount));
+            }
+
+            Runtime::emit_event(SealedBidRevealed {
+                auction_nft_id: self.auction_nft_id.clone(),
+                sealed_bid_id,
+                amount,
+            });
+        }
+
+        // Method allows the instantiator to settle the auction once the reveal window has closed.
+        // The highest revealed bid wins, the earliest one on a tie, and only the winner is settled here.
+        // A hidden reserve has to be revealed with its salt, and a best bid below it means there is no winner.
+        pub fn settle(&mut self, auction_nft: NonFungibleProof, reserve: Option<(Decimal, String)>) -> Vec<Bucket> {
+            self.verify_auction_badge(auction_nft);
+            assert!(!self.settled, "Auction has already been settled");
+            assert!(Runtime::current_epoch().number() >= self.reveal_end_epoch, "Reveal window has not closed yet");
+            self.settled = true;
+
+            let mut winner = self.best_bid;
+            if let (Some(reserve), Some((_, best_bid))) = (reveal_reserve(self.reserve_hash, reserve), winner) {
+                if best_bid < reserve {
+                    winner = None;
+                    Runtime::emit_event(ReserveNotMet {
+                        auction_nft_id: self.auction_nft_id.clone(),
+                        reserve,
+                        best_bid,
+                    });
+                }
+            }
+            self.winning_bid_id = winner.map(|(winning_id, _)| winning_id);
+
+            let offered: Vec<Bucket> = self.offered_resources.values_mut().map(|vault| vault.take_all()).collect();
+            let mut winning_bidder = None;
+            let proceeds = match winner {
+                Some((winning_id, _)) => {
+                    let (bidder, bond, bid) = {
+                        let mut sealed_bid = self.sealed_bids.get_mut(&winning_id).unwrap();
+                        (sealed_bid.bidder, sealed_bid.bond.take_all(), sealed_bid.bid.take_all())
+                    };
+                    deposit_or_hold(&mut self.unclaimed, bidder, bond);
+                    for bucket in offered {
+                        deposit_or_hold(&mut self.unclaimed, bidder, bucket);
+                    }
+                    winning_bidder = Some(bidder);
+                    vec![bid]
+                },
+                // Without a winner the instantiator gets the offered resources back
+                None => offered,
+            };
+
+            Runtime::emit_event(AuctionSettled {
+                auction_nft_id: self.auction_nft_id.clone(),
+                winner: winning_bidder,
+                winning_bid: winner.map_or(Decimal::ZERO, |(_, amount)| amount),
+            });
+
+            proceeds
+        }
+
+        // Returns a losing bid and its bond to the bidder once the auction is settled, anyone can trigger it.
+        // The bond of a bid that was never revealed is forfeited to the instantiator instead.
+        pub fn withdraw_bid(&mut self, sealed_bid_id: u64) {
+            assert!(self.settled, "Auction has not been settled yet");
+            assert!(self.winning_bid_id != Some(sealed_bid_id), "Winning bid is settled with the auction");
+
+            let (bidder, bond, bid, revealed) = {
+                let mut sealed_bid = self.sealed_bids.get_mut(&sealed_bid_id).expect("Sealed bid not found");
+                assert!(!sealed_bid.bond.is_empty(), "Bid has already been withdrawn");
+                (sealed_bid.bidder, sealed_bid.bond.take_all(), sealed_bid.bid.take_all(), sealed_bid.revealed)
+            };
+            if revealed {
+                deposit_or_hold(&mut self.unclaimed, bidder, bond);
+                deposit_or_hold(&mut self.unclaimed, bidder, bid);
+            } else {
+                self.forfeited_bonds.put(bond);
+                bid.drop_empty();
+            }
+        }
+
+        // Method allows the instantiator to take the bonds of the bids that were never revealed
+        pub fn withdraw_forfeited_bonds(&mut self, auction_nft: NonFungibleProof) -> Bucket {
+            self.verify_auction_badge(auction_nft);
+            self.forfeited_bonds.take_all()
+        }
+
+        // Delivers a held refund or winning to the account it belongs to, anyone can trigger it
+        pub fn claim(&mut self, account: Global<Account>, resource_address: ResourceAddress) {
+            claim_held(&mut self.unclaimed, account, resource_address);
+        }
+
+        pub fn get_sealed_bid_count(&self) -> u64 {
+            self.sealed_bid_count
+        }
+
+        pub fn is_settled(&self) -> bool {
+            self.settled
+        }
+
+        fn verify_auction_badge(&self, auction_nft: NonFungibleProof) {
+            let auction_nft = auction_nft.check_with_message(self.auction_nft, "Invalid Auction NFT");
+            assert!(auction_nft.contains_non_fungible(&self.auction_nft_id), "Invalid Auction NFT");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use scrypto::prelude::*;
+
+use crate::escrow::*;
+use crate::settlement::*;
+use crate::types::*;
+
+#[blueprint]
+#[events(StreamStarted, StreamClaimed)]
+mod streaming_escrow {
+    // Sells a fungible resource that is not handed over at once. Once the payment is in, the offered resource
+    // streams to the payee linearly over a number of epochs, and the payee claims whatever has streamed so far.
+    struct StreamingEscrow {
+        offered: Vault,
+        requested_resource: ResourceAddress,
+        requested_amount: Decimal,
+        stream_epochs: u64,
+        payment: Vault,
+        stream_badge: ResourceManager,
+        // Stream handed to the payee, which is set once the payment is in
+        stream_nft_id: Option<NonFungibleLocalId>,
+        start_epoch: Option<u64>,
+        claimed_amount: Decimal,
+        escrow_nft: ResourceAddress,
+        escrow_nft_id: NonFungibleLocalId,
+    }
+
+    impl StreamingEscrow {
+
+        pub fn instantiate_streaming_escrow(
+            offered: Bucket,
+            requested_resource: ResourceAddress,
+            requested_amount: Decimal,
+            stream_epochs: u64
+        ) -> (Global<StreamingEscrow>, NonFungibleBucket) {
+            assert!(offered.resource_address().is_fungible(), "Only fungible resources can be streamed");
+            assert!(!offered.is_empty(), "Offered amount must be positive");
+            assert!(requested_resource.is_fungible(), "Payment has to be made in a fungible resource");
+            assert!(requested_amount > Decimal::ZERO, "Requested amount must be positive");
+            assert!(stream_epochs > 0, "Stream has to last at least one epoch");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(StreamingEscrow::blueprint_id());
+
+            let badge = escrow_badge_builder(component_address)
+                .mint_initial_supply([(1u64.into(), EscrowBadge {
+                    requested_resources: vec![crate::ResourceSpecifier::Fungible {
+                        resource_address: requested_resource,
+                        amount: requested_amount,
+                    }],
+                    offered_resources: IndexMap::from([(offered.resource_address(), offered.amount())]),
+                    created_epoch: Runtime::current_epoch().number(),
+                    expiry_epoch: None,
+                    currency_prices: IndexMap::new(),
+                    escrow_component: component_address,
+                })]);
+            let stream_badge = component_badge_builder::<StreamBadge>(component_address, "Scrypto 101 Stream Badge")
+                .create_with_no_initial_supply();
+
+            let component = Self {
+                offered: Vault::with_bucket(offered),
+                requested_resource,
+                requested_amount,
+                stream_epochs,
+                payment: Vault::new(requested_resource),
+                stream_badge,
+                stream_nft_id: None,
+                start_epoch: None,
+                claimed_amount: Decimal::ZERO,
+                escrow_nft: badge.resource_address(),
+                escrow_nft_id: badge.non_fungible_local_id(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (component, badge)
+        }
+
+        // Takes the payment and starts the stream, returning the stream badge to claim with
+        // and whatever is left of the payment
+        pub fn pay(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            assert!(self.stream_nft_id.is_none(), "Escrow has already been paid");
+            assert!(!self.offered.is_empty(), "Escrow has been cancelled");
+            assert!(payment.resource_address() == self.requested_resource, "Invalid payment resource");
+            assert!(payment.amount() >= self.requested_amount, "Insufficient payment");
+            self.payment.put(payment.take(self.requested_amount));
+
+            let start_epoch = Runtime::current_epoch().number();
+            let amount = self.offered.amount();
+            let stream_nft = self.stream_badge.mint_ruid_non_fungible(StreamBadge {
+                stream_component: Runtime::global_address(),
+                amount,
+                start_epoch,
+                end_epoch: start_epoch + self.stream_epochs,
+            });
+            let stream_nft_id = stream_nft.as_non_fungible().non_fungible_local_id();
+            self.stream_nft_id = Some(stream_nft_id.clone());
+            self.start_epoch = Some(start_epoch);
+
+            Runtime::emit_event(StreamStarted {
+                escrow_nft_id: self.escrow_nft_id.clone(),
+                stream_nft_id,
+                amount,
+                end_epoch: start_epoch + self.stream_epochs,
+            });
+
+            (stream_nft, payment)
+        }
+
+        // Method allows the payee to claim whatever has streamed since their last claim
+        pub fn claim_streamed(&mut self, stream_nft: NonFungibleProof) -> Bucket {
+            let stream_nft = stream_nft.check_with_message(self.stream_badge.address(), "Invalid stream badge");
+            let stream_nft_id = self.stream_nft_id.clone().expect("Escrow has not been paid");
+            assert!(stream_nft.contains_non_fungible(&stream_nft_id), "Invalid stream badge");
+
+            let streamed = self.offered.take_advanced(
+                self.get_claimable_amount(),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            self.claimed_amount += streamed.amount();
+            Runtime::emit_event(StreamClaimed {
+                escrow_nft_id: self.escrow_nft_id.clone(),
+                stream_nft_id,
+                amount: streamed.amount(),
+            });
+
+            streamed
+        }
+
+        // Method allows the instantiator to withdraw the payment, which is not streamed
+        pub fn withdraw_payment(&mut self, escrow_nft: NonFungibleProof) -> Bucket {
+            self.verify_escrow_badge(escrow_nft);
+            self.payment.take_all()
+        }
+
+        // Method allows the instantiator to take the offered resource back as long as nobody has paid for it
+        pub fn cancel(&mut self, escrow_nft: NonFungibleProof) -> Bucket {
+            self.verify_escrow_badge(escrow_nft);
+            assert!(self.stream_nft_id.is_none(), "Escrow has already been paid");
+
+            self.offered.take_all()
+        }
+
+        // Amount that has streamed to the payee so far, claimed or not
+        pub fn get_streamed_amount(&self) -> Decimal {
+            match self.start_epoch {
+                Some(start_epoch) => {
+                    let elapsed_epochs = Runtime::current_epoch().number() - start_epoch;
+                    let total_amount = self.offered.amount() + self.claimed_amount;
+                    total_amount * vested_fraction(elapsed_epochs, self.stream_epochs)
+                },
+                None => Decimal::ZERO,
+            }
+        }
+
+        pub fn get_claimable_amount(&self) -> Decimal {
+            (self.get_streamed_amount() - self.claimed_amount).min(self.offered.amount())
+        }
+
+        pub fn get_claimed_amount(&self) -> Decimal {
+            self.claimed_amount
+        }
+
+        pub fn is_paid(&self) -> bool {
+            self.stream_nft_id.is_some()
+        }
+
+        fn verify_escrow_badge(&self, escrow_nft: NonFungibleProof) {
+            let escrow_nft = escrow_nft.check_with_message(self.escrow_nft, "Invalid Escrow NFT");
+            assert!(escrow_nft.contains_non_fungible(&self.escrow_nft_id), "Invalid Escrow NFT");
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use scrypto::prelude::*;
+
+use crate::types::*;
+use crate::types::ResourceSpecifier;
+
+// External call interface //
+
+// Version of the typed interface below, reported by `get_interface_version`.
+// Changing a method signature of the interface, or a type it passes such as `EscrowConfig`,
+// `EscrowDetails` or `EscrowStatus`, means bumping this.
+pub const ESCROW_INTERFACE_VERSION: u32 = 3;
+
+// Typed client of the Escrow blueprint for components in other packages. It wraps the address of an
+// escrow component, so callers use the argument and return types of this crate instead of method name strings.
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EscrowClient(pub ComponentAddress);
+
+impl EscrowClient {
+
+    // Instantiates an escrow of the package published at the given address
+    pub fn instantiate(
+        package_address: PackageAddress,
+        requested_resources: Vec<ResourceSpecifier>,
+        offered_resources: Vec<Bucket>,
+        config: EscrowConfig,
+        exchange_royalty: RoyaltyAmount
+    ) -> (Self, NonFungibleBucket) {
+        let output = ScryptoVmV1Api::blueprint_call(
+            package_address,
+            "Escrow",
+            "instantiate_escrow",
+            scrypto_args!(requested_resources, offered_resources, config, exchange_royalty),
+        );
+        let (escrow, escrow_nft): (Global<AnyComponent>, NonFungibleBucket) = scrypto_decode(&output).unwrap();
+        (Self(escrow.address()), escrow_nft)
+    }
+
+    // Guards against escrows implementing a different version of the interface than this client
+    pub fn assert_interface_version(&self) {
+        assert!(self.get_interface_version() == ESCROW_INTERFACE_VERSION, "Unsupported escrow interface version");
+    }
+
+    pub fn exchange(&self, payment: Vec<Bucket>) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange", &(payment,))
+    }
+
+    pub fn exchange_with_secret(&self, payment: Vec<Bucket>, secret: Vec<u8>) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_with_secret", &(payment, secret))
+    }
+
+    pub fn exchange_with_terms(&self, payment: Vec<Bucket>, terms_hash: Hash) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_with_terms", &(payment, terms_hash))
+    }
+
+    pub fn exchange_with_referrer(&self, payment: Vec<Bucket>, referrer: ResourceAddress) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_with_referrer", &(payment, referrer))
+    }
+
+    pub fn exchange_with_credential(&self, payment: Vec<Bucket>, credential: NonFungibleProof) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_with_credential", &(payment, credential))
+    }
+
+    // Requires the owner of the account to be present
+    pub fn exchange_as(&self, payment: Vec<Bucket>, account: ComponentAddress) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_as", &(payment, account))
+    }
+
+    pub fn exchange_with_max_price(&self, payment: Vec<Bucket>, max_price: Decimal) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_with_max_price", &(payment, max_price))
+    }
+
+    pub fn exchange_fee_exempt(&self, payment: Vec<Bucket>, exemption: Proof) -> (Vec<Bucket>, Vec<Bucket>) {
+        self.component().call("exchange_fee_exempt", &(payment, exemption))
+    }
+
+    // Delivers the fees the recipient of the fee config refused to its current recipient
+    pub fn claim_fees(&self, resource_address: ResourceAddress) {
+        self.component().call("claim_fees", &(resource_address,))
+    }
+
+    // The resource behind the proof identifies the recipient of the payout table
+    pub fn claim_payout(&self, recipient: Proof) -> Vec<Bucket> {
+        self.component().call("claim_payout", &(recipient,))
+    }
+
+    // Delivers the payout an account recipient of the payout table refused to that account
+    pub fn deliver_payout(&self, account: ComponentAddress) {
+        self.component().call("deliver_payout", &(account,))
+    }
+
+    // The resource behind the proof identifies the creator of the offered NFTs
+    pub fn claim_creator_royalties(&self, creator: Proof) -> Vec<Bucket> {
+        self.component().call("claim_creator_royalties", &(creator,))
+    }
+
+    // Requires a proof of the EscrowBadge in the auth zone
+    pub fn withdraw_resource(&self) -> Vec<Bucket> {
+        self.component().call("withdraw_resource", &())
+    }
+
+    // Requires a proof of the EscrowBadge in the auth zone
+    pub fn cancel_escrow(&self) -> Vec<Bucket> {
+        self.component().call("cancel_escrow", &())
+    }
+
+    // Burns the given EscrowBadge and returns the one that manages the escrow from now on
+    pub fn transfer_ownership(&self, escrow_nft: NonFungibleBucket) -> NonFungibleBucket {
+        self.component().call("transfer_ownership", &(escrow_nft,))
+    }
+
+    // Burns the given EscrowBadge and returns the remaining offered resources with the collected payment
+    pub fn close(&self, escrow_nft: NonFungibleBucket) -> Vec<Bucket> {
+        self.component().call("close", &(escrow_nft,))
+    }
+
+    // Burns the given EscrowBadge and returns the maker shares the escrow is owned by from now on
+    pub fn fractionalize(&self, escrow_nft: NonFungibleBucket, supply: Decimal) -> FungibleBucket {
+        self.component().call("fractionalize", &(escrow_nft, supply))
+    }
+
+    // Burns the given maker shares and returns their part of what the ended escrow held
+    pub fn claim_share(&self, shares: FungibleBucket) -> Vec<Bucket> {
+        self.component().call("claim_share", &(shares,))
+    }
+
+    pub fn get_details(&self) -> EscrowDetails {
+        self.component().call("get_details", &())
+    }
+
+    pub fn get_status(&self) -> EscrowStatus {
+        self.component().call("get_status", &())
+    }
+
+    pub fn get_remaining_amount(&self, resource_address: ResourceAddress) -> Decimal {
+        self.component().call("get_remaining_amount", &(resource_address,))
+    }
+
+    pub fn get_required_payment(&self, resource_address: ResourceAddress, amount: Decimal) -> RequiredPayment {
+        self.component().call("get_required_payment", &(resource_address, amount))
+    }
+
+    pub fn preview_exchange(&self, resource_address: ResourceAddress, amount: Decimal) -> ExchangePreview {
+        self.component().call("preview_exchange", &(resource_address, amount))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.component().call("is_closed", &())
+    }
+
+    pub fn get_interface_version(&self) -> u32 {
+        self.component().call("get_interface_version", &())
+    }
+
+    fn component(&self) -> Global<AnyComponent> {
+        self.0.into()
+    }
+}
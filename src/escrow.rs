@@ -0,0 +1,2989 @@
+use scrypto::prelude::*;
+
+use crate::settlement::*;
+use crate::types::*;
+use crate::types::ResourceSpecifier;
+
+// Sums up the amounts held in a set of buckets, per resource
+pub(crate) fn amounts_of<'a>(buckets: impl IntoIterator<Item = &'a Bucket>) -> IndexMap<ResourceAddress, Decimal> {
+    let mut amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::new();
+    for bucket in buckets {
+        *amounts.entry(bucket.resource_address()).or_insert(Decimal::ZERO) += bucket.amount();
+    }
+    amounts
+}
+
+// Starts the EscrowBadge resource of a component. Badges are numbered from 1 in the order the component
+// mints them, so indexers and manifests can refer to them predictably.
+pub(crate) fn escrow_badge_builder(
+    component_address: ComponentAddress
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        IntegerNonFungibleLocalId,
+        EscrowBadge,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    with_escrow_badge_roles(ResourceBuilder::new_integer_non_fungible::<EscrowBadge>(OwnerRole::None), component_address, None)
+}
+
+fn with_escrow_badge_roles<Y: IsNonFungibleLocalId>(
+    builder: InProgressResourceBuilder<
+        NonFungibleResourceType<Y, EscrowBadge, FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>>,
+    >,
+    component_address: ComponentAddress,
+    bound_account: Option<ComponentAddress>
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<Y, EscrowBadge, FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>>,
+> {
+    // Only the component can recall an EscrowBadge, which it does for escrows with a recovery authority
+    let builder = with_component_roles(builder, component_address, "Scrypto 101 Escrow Badge")
+        .recall_roles(recall_roles! {
+            recaller => rule!(require(global_caller(component_address)));
+            recaller_updater => rule!(deny_all);
+        });
+    // A soulbound badge can only be deposited to the account it is bound to, which a recovery returns it to as well
+    match bound_account {
+        Some(account) => builder.deposit_roles(deposit_roles! {
+            depositor => rule!(require(global_caller(account)));
+            depositor_updater => rule!(deny_all);
+        }),
+        None => builder,
+    }
+}
+
+// Creates the EscrowBadge resource of a standalone escrow with its first badge, under the chosen local ID scheme
+pub(crate) fn mint_first_escrow_badge(
+    component_address: ComponentAddress,
+    badge_id_kind: &BadgeIdKind,
+    bound_account: Option<ComponentAddress>,
+    badge: EscrowBadge
+) -> NonFungibleBucket {
+    match badge_id_kind {
+        BadgeIdKind::Integer => with_escrow_badge_roles(
+            ResourceBuilder::new_integer_non_fungible::<EscrowBadge>(OwnerRole::None),
+            component_address,
+            bound_account
+        )
+            .mint_initial_supply([(1u64.into(), badge)]),
+        BadgeIdKind::Ruid => with_escrow_badge_roles(
+            ResourceBuilder::new_ruid_non_fungible::<EscrowBadge>(OwnerRole::None),
+            component_address,
+            bound_account
+        )
+            .mint_initial_supply([badge]),
+        BadgeIdKind::Label(label) => with_escrow_badge_roles(
+            ResourceBuilder::new_string_non_fungible::<EscrowBadge>(OwnerRole::None),
+            component_address,
+            bound_account
+        )
+            .mint_initial_supply([(StringNonFungibleLocalId::new(label.clone()).expect("Invalid badge label"), badge)]),
+    }
+}
+
+// Mints the n-th EscrowBadge of a resource, e.g. when ownership is transferred. Labels get the number appended,
+// since the local ID of a burnt badge cannot be minted again.
+pub(crate) fn mint_escrow_badge(
+    escrow_nft: ResourceManager,
+    badge_id_kind: &BadgeIdKind,
+    badge_number: u64,
+    badge: EscrowBadge
+) -> NonFungibleBucket {
+    let badge = match badge_id_kind {
+        BadgeIdKind::Integer => escrow_nft.mint_non_fungible(&NonFungibleLocalId::integer(badge_number), badge),
+        BadgeIdKind::Ruid => escrow_nft.mint_ruid_non_fungible(badge),
+        BadgeIdKind::Label(label) => {
+            let escrow_nft_id = NonFungibleLocalId::string(format!("{}_{}", label, badge_number)).expect("Invalid badge label");
+            escrow_nft.mint_non_fungible(&escrow_nft_id, badge)
+        },
+    };
+    badge.as_non_fungible()
+}
+
+// Starts the TakerBadge resource of a component
+pub(crate) fn taker_badge_builder(
+    component_address: ComponentAddress
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        RUIDNonFungibleLocalId,
+        TakerBadge,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    component_badge_builder::<TakerBadge>(component_address, "Scrypto 101 Escrow Taker Badge")
+}
+
+// Starts the TakerReceipt resource of a component, whose receipts cannot be withdrawn when soulbound
+pub(crate) fn taker_receipt_builder(
+    component_address: ComponentAddress,
+    taker_receipts: TakerReceipts
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        RUIDNonFungibleLocalId,
+        TakerReceipt,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    let builder = component_badge_builder::<TakerReceipt>(component_address, "Scrypto 101 Escrow Receipt");
+    match taker_receipts {
+        TakerReceipts::Transferable => builder,
+        TakerReceipts::Soulbound => builder.withdraw_roles(withdraw_roles! {
+            withdrawer => rule!(deny_all);
+            withdrawer_updater => rule!(deny_all);
+        }),
+    }
+}
+
+// Starts the ReputationAttestation resource of a component. Attestations are soulbound, so a track record
+// cannot be bought.
+pub(crate) fn reputation_attestation_builder(
+    component_address: ComponentAddress
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        RUIDNonFungibleLocalId,
+        ReputationAttestation,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    component_badge_builder::<ReputationAttestation>(component_address, "Scrypto 101 Escrow Reputation Attestation")
+        .withdraw_roles(withdraw_roles! {
+            withdrawer => rule!(deny_all);
+            withdrawer_updater => rule!(deny_all);
+        })
+}
+
+// Starts the SettlementClaim resource of a component
+pub(crate) fn settlement_claim_builder(
+    component_address: ComponentAddress
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        RUIDNonFungibleLocalId,
+        SettlementClaim,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    component_badge_builder::<SettlementClaim>(component_address, "Scrypto 101 Escrow Settlement Claim")
+}
+
+// Starts a resource of tickets numbered from 1 in the order they are handed out, e.g. the contributions
+// to a crowdfund. The metadata links wallets back to the component the tickets are redeemed at.
+pub(crate) fn ticket_builder<T: NonFungibleData>(
+    component_address: ComponentAddress,
+    name: &str
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        IntegerNonFungibleLocalId,
+        T,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    let component_rule = rule!(require(global_caller(component_address)));
+
+    ResourceBuilder::new_integer_non_fungible::<T>(OwnerRole::None)
+        .metadata(metadata! {
+            init {
+                "name" => name.to_owned(), locked;
+                "escrow_component" => GlobalAddress::from(component_address), locked;
+            }
+        })
+        .mint_roles(mint_roles! {
+            minter => component_rule.clone();
+            minter_updater => rule!(deny_all);
+        })
+        .burn_roles(burn_roles! {
+            burner => component_rule;
+            burner_updater => rule!(deny_all);
+        })
+}
+
+// Starts a badge resource of a component, which is the only one allowed to mint, burn or update badges
+pub(crate) fn component_badge_builder<T: NonFungibleData>(
+    component_address: ComponentAddress,
+    name: &str
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<
+        RUIDNonFungibleLocalId,
+        T,
+        FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>,
+    >,
+> {
+    with_component_roles(ResourceBuilder::new_ruid_non_fungible::<T>(OwnerRole::None), component_address, name)
+}
+
+// Names a badge resource and leaves minting, burning and updating its badges to the component
+pub(crate) fn with_component_roles<Y: IsNonFungibleLocalId, T: NonFungibleData>(
+    builder: InProgressResourceBuilder<
+        NonFungibleResourceType<Y, T, FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>>,
+    >,
+    component_address: ComponentAddress,
+    name: &str
+) -> InProgressResourceBuilder<
+    NonFungibleResourceType<Y, T, FixedEnumVariant<NON_FUNGIBLE_DATA_SCHEMA_VARIANT_LOCAL, LocalNonFungibleDataSchema>>,
+> {
+    let component_rule = rule!(require(global_caller(component_address)));
+
+    builder
+        .metadata(metadata! {
+            init {
+                "name" => name.to_owned(), locked;
+            }
+        })
+        .mint_roles(mint_roles! {
+            minter => component_rule.clone();
+            minter_updater => rule!(deny_all);
+        })
+        .burn_roles(burn_roles! {
+            burner => component_rule.clone();
+            burner_updater => rule!(deny_all);
+        })
+        .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+            non_fungible_data_updater => component_rule;
+            non_fungible_data_updater_updater => rule!(deny_all);
+        })
+}
+
+// Works out how much of every resource was taken out of a set of buckets, given what is left of them
+pub(crate) fn amounts_taken(
+    amounts: IndexMap<ResourceAddress, Decimal>,
+    left: &[Bucket]
+) -> IndexMap<ResourceAddress, Decimal> {
+    let left_amounts = amounts_of(left);
+    amounts
+        .into_iter()
+        .map(|(address, amount)| (address, amount - left_amounts.get(&address).copied().unwrap_or(Decimal::ZERO)))
+        .filter(|(_, amount)| *amount > Decimal::ZERO)
+        .collect()
+}
+
+// Merges a set of buckets into one bucket per resource
+pub(crate) fn group_by_resource(buckets: Vec<Bucket>) -> IndexMap<ResourceAddress, Bucket> {
+    let mut grouped: IndexMap<ResourceAddress, Bucket> = IndexMap::new();
+    for bucket in buckets {
+        match grouped.get_mut(&bucket.resource_address()) {
+            Some(existing) => existing.put(bucket),
+            None => {
+                grouped.insert(bucket.resource_address(), bucket);
+            }
+        }
+    }
+    grouped
+}
+
+// Stores a set of buckets with one vault per resource
+pub(crate) fn vaults_of(buckets: Vec<Bucket>) -> IndexMap<ResourceAddress, Vault> {
+    let mut vaults: IndexMap<ResourceAddress, Vault> = IndexMap::new();
+    for bucket in buckets {
+        match vaults.get_mut(&bucket.resource_address()) {
+            Some(vault) => vault.put(bucket),
+            None => {
+                vaults.insert(bucket.resource_address(), Vault::with_bucket(bucket));
+            }
+        }
+    }
+    vaults
+}
+
+// Seals an amount behind a salt, e.g. a sealed bid or the hidden reserve price of an auction
+pub(crate) fn commitment_of(amount: Decimal, salt: String) -> Hash {
+    hash(scrypto_encode(&(amount, salt)).unwrap())
+}
+
+// Checks the reserve price revealed at the settlement of an auction against its commitment.
+// Auctions without a hidden reserve have nothing to reveal.
+pub(crate) fn reveal_reserve(reserve_hash: Option<Hash>, reserve: Option<(Decimal, String)>) -> Option<Decimal> {
+    let reserve_hash = reserve_hash?;
+    let (reserve, salt) = reserve.expect("The reserve price has to be revealed to settle");
+    assert!(commitment_of(reserve, salt) == reserve_hash, "Reserve price does not match its commitment");
+    Some(reserve)
+}
+
+// Pushes a bucket to an account, holding it for a later claim if the account refuses the deposit
+pub(crate) fn deposit_or_hold(
+    unclaimed: &mut KeyValueStore<(ComponentAddress, ResourceAddress), Vault>,
+    account_address: ComponentAddress,
+    bucket: Bucket
+) {
+    let mut account: Global<Account> = account_address.into();
+    if let Some(refused) = account.try_deposit_or_refund(bucket, None) {
+        let key = (account_address, refused.resource_address());
+        if unclaimed.get(&key).is_none() {
+            unclaimed.insert(key, Vault::new(refused.resource_address()));
+        }
+        unclaimed.get_mut(&key).unwrap().put(refused);
+    }
+}
+
+// Delivers a held bucket to the account it belongs to
+pub(crate) fn claim_held(
+    unclaimed: &mut KeyValueStore<(ComponentAddress, ResourceAddress), Vault>,
+    mut account: Global<Account>,
+    resource_address: ResourceAddress
+) {
+    let bucket = unclaimed
+        .get_mut(&(account.address(), resource_address))
+        .expect("Nothing to claim")
+        .take_all();
+    account.try_deposit_or_abort(bucket, None);
+}
+
+// Reads the balance of every vault, per resource
+pub(crate) fn amounts_in(vaults: &IndexMap<ResourceAddress, Vault>) -> IndexMap<ResourceAddress, Decimal> {
+    vaults.iter().map(|(address, vault)| (*address, vault.amount())).collect()
+}
+
+// Number of decimal places a fungible resource can be divided into
+pub(crate) fn divisibility_of(resource_address: ResourceAddress) -> u8 {
+    ResourceManager::from(resource_address)
+        .resource_type()
+        .divisibility()
+        .expect("Resource is not fungible")
+}
+
+// Checks that a resource is the liquid stake unit of a validator, which names its LSU resource in its metadata.
+// Casting to a validator rejects components of any other blueprint.
+pub(crate) fn assert_liquid_stake_unit(validator: ComponentAddress, resource_address: ResourceAddress) {
+    let validator: Global<Validator> = validator.into();
+    let pool_unit: Option<GlobalAddress> = validator.get_metadata("pool_unit").expect("Invalid validator metadata");
+    assert!(
+        pool_unit == Some(resource_address.into()),
+        "Requested resource is not a liquid stake unit of the validator"
+    );
+}
+
+// Unstake claim NFT resource of a validator, named in its metadata
+pub(crate) fn claim_nft_of(validator: ComponentAddress) -> ResourceAddress {
+    let validator: Global<Validator> = validator.into();
+    let claim_nft: Option<GlobalAddress> = validator.get_metadata("claim_nft").expect("Invalid validator metadata");
+    ResourceAddress::try_from(claim_nft.expect("Validator has no claim NFT")).unwrap()
+}
+
+// Royalty the creator of an NFT resource asks for, read from its metadata or a royalty registry. Malformed
+// metadata or registry answers count as no royalty, so the creator cannot make listings unfillable.
+pub(crate) fn creator_royalty_of(source: &CreatorRoyaltySource, resource_address: ResourceAddress) -> Option<CreatorRoyalty> {
+    match source {
+        CreatorRoyaltySource::Metadata => {
+            let resource_manager = ResourceManager::from(resource_address);
+            let royalty_bps: u32 = resource_manager.get_metadata("creator_royalty_bps").ok().flatten()?;
+            let creator_badge: GlobalAddress = resource_manager.get_metadata("creator_royalty_badge").ok().flatten()?;
+            Some(CreatorRoyalty {
+                creator_badge: ResourceAddress::try_from(creator_badge).ok()?,
+                royalty_bps: u16::try_from(royalty_bps).unwrap_or(u16::MAX),
+            })
+        },
+        CreatorRoyaltySource::Registry(registry) => {
+            let output = ScryptoVmV1Api::object_call(
+                registry.as_node_id(),
+                "get_creator_royalty",
+                scrypto_encode(&(resource_address,)).unwrap(),
+            );
+            scrypto_decode::<Option<CreatorRoyalty>>(&output).ok().flatten()
+        },
+    }
+}
+
+// Pool unit resource and pooled resources of a native pool, named in its metadata
+pub(crate) fn pool_resources_of(pool: ComponentAddress) -> (ResourceAddress, Vec<ResourceAddress>) {
+    let pool: Global<AnyComponent> = pool.into();
+    let pool_unit: GlobalAddress = pool.get_metadata("pool_unit").expect("Invalid pool metadata").expect("Pool has no pool unit");
+    let pool_resources: Vec<GlobalAddress> = pool.get_metadata("pool_resources").expect("Invalid pool metadata").unwrap_or_default();
+    (
+        ResourceAddress::try_from(pool_unit).unwrap(),
+        pool_resources.into_iter().map(|address| ResourceAddress::try_from(address).unwrap()).collect()
+    )
+}
+
+// Amount of the underlying resource the given pool units currently redeem for
+pub(crate) fn pool_redemption_value(pool: ComponentAddress, underlying: ResourceAddress, pool_units: Decimal) -> Decimal {
+    let redemption_value = match ScryptoVmV1Api::object_get_blueprint_id(pool.as_node_id()).blueprint_name.as_str() {
+        "OneResourcePool" => return Global::<OneResourcePool>::from(pool).get_redemption_value(pool_units),
+        "TwoResourcePool" => Global::<TwoResourcePool>::from(pool).get_redemption_value(pool_units),
+        "MultiResourcePool" => Global::<MultiResourcePool>::from(pool).get_redemption_value(pool_units),
+        _ => panic!("Not a native pool"),
+    };
+    *redemption_value.get(&underlying).expect("Pool does not hold the underlying resource")
+}
+
+// What a taker brings to a fill besides the payment, each of which only some escrows ask for
+#[derive(Default)]
+pub struct TakerInput {
+    pub secret: Option<Vec<u8>>,
+    pub terms_hash: Option<Hash>,
+    pub referrer: Option<ResourceAddress>,
+    pub credential: Option<NonFungibleProof>,
+    pub account: Option<ComponentAddress>,
+    pub max_price: Option<Decimal>,
+}
+
+// Resources a component mints to the parties of its escrows, the optional ones only for escrows configured to
+pub struct EscrowResources {
+    pub taker_badge: ResourceAddress,
+    pub taker_receipt: Option<ResourceAddress>,
+    pub reputation_attestation: Option<ResourceAddress>,
+    pub settlement_claim: Option<ResourceAddress>,
+}
+
+// Escrow entries //
+
+// State and settlement logic of a single escrow, shared by every blueprint that holds escrows
+#[derive(ScryptoSbor)]
+pub struct EscrowEntry {
+    pub(crate) escrow_nft_id: NonFungibleLocalId,
+    pub(crate) requested_resources: Vec<ResourceSpecifier>,
+    offered_resources: IndexMap<ResourceAddress, Vault>,
+    requested_resource_vaults: IndexMap<ResourceAddress, Vault>,
+    config: EscrowConfig,
+    filled_amounts: IndexMap<ResourceAddress, Decimal>,
+    status: EscrowStatus,
+    counter_offers: KeyValueStore<u64, CounterOffer>,
+    counter_offer_count: u64,
+    first_fill_epoch: Option<u64>,
+    // Referral fees waiting for their referrer, per referrer resource and then per paid resource
+    referral_vaults: IndexMap<ResourceAddress, IndexMap<ResourceAddress, Vault>>,
+    // Proceeds split off for badge recipients of the payout table, or refused by account recipients, per recipient
+    // and then per paid resource
+    payout_vaults: IndexMap<PayoutRecipient, IndexMap<ResourceAddress, Vault>>,
+    // Royalties owed to NFT creators, per creator badge resource and then per paid resource
+    creator_vaults: IndexMap<ResourceAddress, IndexMap<ResourceAddress, Vault>>,
+    // Proposer of the accepted counter-offer, both sides are committed to the deal from then on
+    engaged_counterparty: Option<ComponentAddress>,
+    // Leg of the taker of a two-sided escrow, or payment waiting for the instantiator's approval
+    taker_deposit: IndexMap<ResourceAddress, Vault>,
+    approval_deadline_epoch: Option<u64>,
+    withdrawn_amounts: IndexMap<ResourceAddress, Decimal>,
+    pub(crate) taker_badge: ResourceAddress,
+    pub(crate) taker_nft_id: Option<NonFungibleLocalId>,
+    taker_receipt: Option<ResourceAddress>,
+    reputation_attestation: Option<ResourceAddress>,
+    settlement_claim: Option<ResourceAddress>,
+    // Resources owed to the holders of settlement claims, per claim
+    claim_vaults: KeyValueStore<NonFungibleLocalId, IndexMap<ResourceAddress, Vault>>,
+    // Amount each taker of a capped escrow has paid so far
+    taker_fills: KeyValueStore<TakerId, Decimal>,
+    disputed: bool,
+    refund_vaults: IndexMap<ResourceAddress, Vault>,
+    // Collateral posted by each side, and what the arbiter earned slashing it
+    maker_bond: Option<Vault>,
+    taker_bond: Option<Vault>,
+    arbiter_bond_cut: Option<Vault>,
+    // Premium paid by the insured party, until the insurer takes it by underwriting the coverage
+    premium: Option<Vault>,
+    coverage: Option<Vault>,
+    underwritten_epoch: Option<u64>,
+    coverage_triggered: bool,
+    // Oracle prices the escrow recorded for its own time-weighted average, oldest first
+    price_observations: Vec<(u64, Decimal)>,
+    dispute_votes: IndexMap<NonFungibleLocalId, DisputeAward>,
+    chosen_alternative: Option<ResourceSpecifier>,
+    paused: bool,
+    recovery_requested_epoch: Option<u64>,
+}
+
+impl EscrowEntry {
+
+    pub fn new(
+        escrow_nft_id: NonFungibleLocalId,
+        requested_resources: Vec<ResourceSpecifier>,
+        offered_resources: Vec<Bucket>,
+        config: EscrowConfig,
+        resources: EscrowResources
+    ) -> Self {
+        let EscrowResources { taker_badge, taker_receipt, reputation_attestation, settlement_claim } = resources;
+
+        // The deadline, if any, has to be in the future
+        if let Some(expiry_epoch) = config.expiry_epoch {
+            assert!(expiry_epoch > Runtime::current_epoch().number(), "Expiry epoch must be in the future");
+        }
+        if let Some(expiry_time) = config.expiry_time {
+            assert!(!expiry_time.has_passed(), "Expiry time must be in the future");
+        }
+
+        assert!(!offered_resources.is_empty(), "At least one offered resource is required");
+
+        // Disputes are raised by the one taker of an escrow, so arbitrated escrows are filled in one go
+        if config.arbiter_badge.is_some() {
+            assert!(!config.allow_partial_fills, "Arbitrated escrows cannot be partially filled");
+        }
+        if config.arbiter_threshold > 1 {
+            let arbiter_badge = config.arbiter_badge.expect("An arbiter council requires an arbiter badge");
+            assert!(!arbiter_badge.is_fungible(), "Arbiter councils require a non-fungible arbiter badge");
+        }
+        if config.dispute_window_epochs.is_some() {
+            assert!(config.arbiter_badge.is_some(), "A dispute window requires an arbiter");
+        }
+
+        if let Some(vesting_epochs) = config.vesting_epochs {
+            assert!(vesting_epochs > 0, "Vesting has to last at least one epoch");
+        }
+
+        if let Some(dutch_auction) = &config.dutch_auction {
+            assert!(!config.allow_partial_fills, "Dutch auctions cannot be partially filled");
+            assert!(dutch_auction.floor_amount > Decimal::ZERO, "Floor amount must be positive");
+            assert!(dutch_auction.start_amount >= dutch_auction.floor_amount, "Start amount cannot be below the floor");
+            assert!(dutch_auction.end_epoch > dutch_auction.start_epoch, "End epoch must be after the start epoch");
+            assert!(dutch_auction.step_epochs > 0, "Price steps must last at least one epoch");
+        }
+
+        // The expiry is the timeout after which a hash-locked escrow is refunded
+        if config.hash_lock.is_some() {
+            assert!(config.expiry_epoch.is_some(), "Hash-locked escrows require an expiry epoch");
+            assert!(config.invite_code_hash.is_none(), "An escrow is either hash-locked or invite-only");
+        }
+
+        if let Some(oracle_pricing) = &config.oracle_pricing {
+            assert!(config.dutch_auction.is_none(), "An escrow is either a Dutch auction or oracle-priced");
+            assert!(!config.allow_partial_fills, "Oracle-priced escrows cannot be partially filled");
+            assert!(oracle_pricing.quote_value > Decimal::ZERO, "Quote value must be positive");
+            if let Some(twap_guard) = &oracle_pricing.twap_guard {
+                assert!(twap_guard.max_deviation_bps <= MAX_FEE_BPS, "Maximum deviation cannot exceed 100%");
+                assert!(
+                    twap_guard.source != TwapSource::Observations { window_epochs: 0 },
+                    "Observation window must span at least one epoch"
+                );
+            }
+        }
+
+        // Per-unit escrows let takers choose their size, releasing one offered unit per unit price paid
+        if config.per_unit_pricing {
+            assert!(config.allow_partial_fills, "Per-unit pricing requires partial fills");
+            assert!(
+                config.dutch_auction.is_none() && config.oracle_pricing.is_none() && config.currency_prices.is_empty(),
+                "Per-unit pricing cannot be combined with other pricing"
+            );
+            assert!(offered_resources.len() == 1, "Per-unit pricing requires a single offered resource");
+        }
+        if let Some(bonding_curve) = &config.bonding_curve {
+            assert!(config.per_unit_pricing, "Bonding curves require per-unit pricing");
+            match bonding_curve {
+                BondingCurve::Linear { slope } => assert!(*slope >= Decimal::ZERO, "Curve slope cannot be negative"),
+                BondingCurve::Exponential { growth_factor, step_amount } => {
+                    assert!(*growth_factor >= Decimal::ONE, "Growth factor must be at least 1");
+                    assert!(*step_amount > Decimal::ZERO, "Step amount must be positive");
+                },
+                BondingCurve::Tranches { tranches } => {
+                    assert!(!tranches.is_empty(), "At least one tranche is required");
+                    assert!(
+                        matches!(
+                            requested_resources.as_slice(),
+                            [ResourceSpecifier::Fungible { amount, .. }] if *amount == tranches[0].price
+                        ),
+                        "The requested amount has to be the price of the first tranche"
+                    );
+                    assert!(
+                        tranches.iter().all(|tranche| tranche.amount > Decimal::ZERO && tranche.price > Decimal::ZERO),
+                        "Tranche amounts and prices must be positive"
+                    );
+                    assert!(
+                        tranches.windows(2).all(|pair| pair[0].price <= pair[1].price),
+                        "Later tranches cannot be cheaper than earlier ones"
+                    );
+                },
+            }
+        }
+
+        // The beneficiary takes over only once an expired escrow has been left alone
+        if config.beneficiary.is_some() {
+            assert!(config.expiry_epoch.is_some(), "A beneficiary requires an expiry epoch");
+            assert!(config.hash_lock.is_none(), "Hash-locked escrows are refunded to their refund account");
+        }
+
+        assert!(
+            taker_receipt.is_some() == config.taker_receipts.is_some(),
+            "Taker receipts require a receipt resource"
+        );
+        assert!(
+            reputation_attestation.is_some() == config.reputation_attestations,
+            "Reputation attestations require an attestation resource"
+        );
+        assert!(
+            settlement_claim.is_some() == config.claim_settlement,
+            "Claim settlement requires a claim resource"
+        );
+
+        if let Some(expiry_reclaim) = &config.expiry_reclaim {
+            assert!(config.expiry_epoch.is_some(), "Reclaiming expired escrows requires an expiry epoch");
+            assert!(
+                config.hash_lock.is_none() && config.beneficiary.is_none(),
+                "Expired escrows are either reclaimed, refunded or paid to a beneficiary"
+            );
+            assert!(expiry_reclaim.bounty_bps <= MAX_FEE_BPS, "Bounty cannot exceed 100%");
+        }
+
+        // Two-sided and approved escrows swap fixed legs, there is no price to work out at settlement
+        if config.two_sided || config.maker_approval_epochs.is_some() {
+            assert!(
+                !(config.two_sided && config.maker_approval_epochs.is_some()),
+                "An escrow is either two-sided or settled on approval"
+            );
+            assert!(
+                !config.allow_partial_fills
+                    && !config.per_unit_pricing
+                    && config.dutch_auction.is_none()
+                    && config.oracle_pricing.is_none()
+                    && config.currency_prices.is_empty()
+                    && config.alternative_resources.is_empty(),
+                "Deposited legs settle fixed terms"
+            );
+            assert!(
+                config.arbiter_badge.is_none() && config.hash_lock.is_none(),
+                "Deposited legs cannot be arbitrated or hash-locked"
+            );
+        }
+        if let Some(maker_approval_epochs) = config.maker_approval_epochs {
+            assert!(maker_approval_epochs > 0, "Approval window has to last at least one epoch");
+        }
+
+        if let Some(collateral_bond) = &config.collateral_bond {
+            assert!(config.arbiter_badge.is_some(), "Collateral bonds require an arbiter");
+            assert!(collateral_bond.resource_address.is_fungible(), "Collateral bonds have to be fungible");
+            assert!(collateral_bond.amount > Decimal::ZERO, "Bond amount must be positive");
+            assert!(
+                collateral_bond.slash_bps <= MAX_FEE_BPS && collateral_bond.arbiter_bps <= MAX_FEE_BPS,
+                "Slashing cannot exceed 100%"
+            );
+        }
+
+        if let Some(insurance) = &config.insurance {
+            assert!(config.arbiter_badge.is_some(), "Insurance requires an arbiter");
+            assert!(
+                insurance.premium_resource.is_fungible() && insurance.coverage_resource.is_fungible(),
+                "Premium and coverage have to be fungible"
+            );
+            assert!(
+                insurance.premium_amount > Decimal::ZERO && insurance.coverage_amount > Decimal::ZERO,
+                "Premium and coverage must be positive"
+            );
+            assert!(insurance.coverage_epochs > 0, "Coverage has to last at least one epoch");
+        }
+
+        assert!(config.referral_fee_bps <= MAX_FEE_BPS, "Referral fee cannot exceed 100%");
+        assert!(config.max_fee_bps <= MAX_FEE_BPS, "Fee rate cannot exceed 100%");
+        if let Some(creator_royalty) = &config.creator_royalty {
+            assert!(
+                offered_resources.len() == 1 && !offered_resources[0].resource_address().is_fungible(),
+                "Creator royalties need a single offered NFT resource"
+            );
+            assert!(
+                u32::from(creator_royalty.max_royalty_bps) + u32::from(config.referral_fee_bps) <= u32::from(MAX_FEE_BPS),
+                "Creator royalty and referral fee cannot exceed 100%"
+            );
+        }
+        assert!(config.cancellation_penalty_bps <= MAX_FEE_BPS, "Cancellation penalty cannot exceed 100%");
+
+        if let Some(badge_recovery) = &config.badge_recovery {
+            assert!(badge_recovery.delay_epochs > 0, "Badge recovery has to be delayed by at least one epoch");
+        }
+        if let Some(bound_account) = config.soulbound_badge {
+            // Fails if the address is not an account
+            let _: Global<Account> = bound_account.into();
+        }
+
+        // Multi-currency escrows price the whole offer in every accepted currency
+        for (resource_address, price) in &config.currency_prices {
+            assert!(resource_address.is_fungible(), "Accepted currencies have to be fungible");
+            assert!(*price > Decimal::ZERO, "Currency prices must be positive");
+        }
+        if !config.currency_prices.is_empty() {
+            assert!(
+                config.dutch_auction.is_none() && config.oracle_pricing.is_none() && config.alternative_resources.is_empty(),
+                "Multi-currency pricing cannot be combined with dynamic pricing or alternatives"
+            );
+        }
+
+        // Either-or escrows are settled in one go, on whichever fixed terms the taker pays
+        if !config.alternative_resources.is_empty() {
+            assert!(
+                !config.allow_partial_fills && config.dutch_auction.is_none() && config.oracle_pricing.is_none(),
+                "Alternative requested resources cannot be combined with partial fills or dynamic pricing"
+            );
+            for alternative in &config.alternative_resources {
+                match alternative {
+                    ResourceSpecifier::Fungible { .. } => {},
+                    ResourceSpecifier::NonFungible { non_fungible_local_ids, .. } => {
+                        assert!(!non_fungible_local_ids.is_empty(), "At least one non-fungible has to be requested");
+                    },
+                    ResourceSpecifier::NonFungibleCount { resource_address, count } => {
+                        assert!(!resource_address.is_fungible(), "Counted requests require a non-fungible resource");
+                        assert!(*count > 0, "At least one non-fungible has to be requested");
+                    },
+                }
+            }
+        }
+
+        // LSU escrows ask for one validator's stake units, not whatever the payment turns out to be
+        if let Some(validator) = config.lsu_validator {
+            match requested_resources.as_slice() {
+                [ResourceSpecifier::Fungible { resource_address, .. }] => assert_liquid_stake_unit(validator, *resource_address),
+                _ => panic!("LSU escrows request a single fungible resource"),
+            }
+            assert!(
+                config.currency_prices.is_empty() && config.alternative_resources.is_empty(),
+                "LSU escrows cannot accept other currencies or alternatives"
+            );
+        }
+
+        if let Some(unstake_claims) = &config.unstake_claims {
+            assert!(
+                matches!(
+                    requested_resources.as_slice(),
+                    [ResourceSpecifier::NonFungibleCount { resource_address, .. }]
+                        if *resource_address == claim_nft_of(unstake_claims.validator)
+                ),
+                "Unstake claim escrows request a count of the validator's claim NFTs"
+            );
+            assert!(unstake_claims.min_claim_amount > Decimal::ZERO, "Minimum claim amount must be positive");
+            assert!(
+                config.alternative_resources.is_empty(),
+                "Unstake claim escrows cannot accept alternatives"
+            );
+        }
+
+        // Casting checks that the components are what they claim to be
+        if let Some(proceeds_locker) = &config.proceeds_locker {
+            let _: Global<AccountLocker> = proceeds_locker.locker.into();
+            let _: Global<Account> = proceeds_locker.account.into();
+        }
+        if let Some(maker_account) = config.maker_account {
+            assert!(config.proceeds_locker.is_none(), "Proceeds go either to a locker or to the maker account");
+            let _: Global<Account> = maker_account.into();
+        }
+
+        // Refused deposits to the maker account would be split a second time on their next withdrawal
+        if !config.payout_split.is_empty() {
+            assert!(config.maker_account.is_none(), "Split proceeds cannot be pushed to the maker account");
+            assert!(
+                config.payout_split.values().all(|share_bps| *share_bps > 0),
+                "Payout shares must be positive"
+            );
+            assert!(
+                config.payout_split.values().map(|share_bps| u32::from(*share_bps)).sum::<u32>() <= u32::from(MAX_FEE_BPS),
+                "Payout shares cannot exceed 100%"
+            );
+            for recipient in config.payout_split.keys() {
+                if let PayoutRecipient::Account(account) = recipient {
+                    let _: Global<Account> = (*account).into();
+                }
+            }
+        }
+
+        if let Some(dex_conversion) = &config.dex_conversion {
+            let requested_resource = match requested_resources.as_slice() {
+                [ResourceSpecifier::Fungible { resource_address, .. }] => *resource_address,
+                _ => panic!("DEX conversion needs a single fungible requested resource"),
+            };
+            assert!(!dex_conversion.min_rates.is_empty(), "DEX conversion needs at least one input token");
+            assert!(
+                dex_conversion.min_rates
+                    .iter()
+                    .all(|(input_resource, min_rate)| *input_resource != requested_resource && *min_rate > Decimal::ZERO),
+                "Input tokens need a positive minimum rate and must differ from the requested resource"
+            );
+            assert!(
+                config.currency_prices.is_empty() && config.alternative_resources.is_empty(),
+                "DEX conversion cannot be combined with other currencies or alternatives"
+            );
+        }
+
+        if let Some(pool_units) = &config.pool_units {
+            let (pool_unit, pool_resources) = pool_resources_of(pool_units.pool);
+            assert!(
+                matches!(
+                    requested_resources.as_slice(),
+                    [ResourceSpecifier::Fungible { resource_address, .. }] if *resource_address == pool_unit
+                ),
+                "Pool unit escrows request a single amount of the pool's units"
+            );
+            assert!(pool_resources.contains(&pool_units.underlying), "Pool does not hold the underlying resource");
+            assert!(pool_units.min_value_per_unit > Decimal::ZERO, "Minimum value per unit must be positive");
+            assert!(
+                config.currency_prices.is_empty() && config.alternative_resources.is_empty(),
+                "Pool unit escrows cannot accept other currencies or alternatives"
+            );
+        }
+
+        // Partial fills release a proportional slice of the offered resources, so they have to be fungible
+        if config.allow_partial_fills {
+            assert!(
+                offered_resources.iter().all(|bucket| bucket.resource_address().is_fungible()),
+                "Partial fills require fungible offered resources"
+            );
+        }
+
+        if let Some(min_fill_amount) = config.min_fill_amount {
+            assert!(config.allow_partial_fills, "Minimum fill amounts only apply to partially fillable escrows");
+            assert!(min_fill_amount > Decimal::ZERO, "Minimum fill amount must be positive");
+        }
+
+        if config.fill_or_kill {
+            assert!(config.allow_partial_fills, "Fill-or-kill only applies to partially fillable escrows");
+            assert!(config.sale_cap.is_none(), "Fill-or-kill escrows cannot be sale-capped");
+        }
+
+        if let Some(sale_cap) = config.sale_cap {
+            assert!(sale_cap > Decimal::ZERO, "Sale cap must be positive");
+            assert!(
+                config.allow_partial_fills
+                    && matches!(requested_resources.as_slice(), [ResourceSpecifier::Fungible { .. }])
+                    && config.currency_prices.is_empty()
+                    && config.alternative_resources.is_empty(),
+                "Sale caps require partial fills of a single fungible requested resource"
+            );
+        }
+
+        // Caps only make sense when a taker can come back for more of a single fungible leg
+        if let Some(per_taker_cap) = config.per_taker_cap {
+            assert!(per_taker_cap > Decimal::ZERO, "Per-taker cap must be positive");
+            assert!(
+                config.allow_partial_fills
+                    && matches!(requested_resources.as_slice(), [ResourceSpecifier::Fungible { .. }])
+                    && config.currency_prices.is_empty()
+                    && config.alternative_resources.is_empty(),
+                "Per-taker caps require partial fills of a single fungible requested resource"
+            );
+        }
+
+        let bond_resource = config.collateral_bond.as_ref().map(|collateral_bond| collateral_bond.resource_address);
+        let (premium, coverage) = match &config.insurance {
+            Some(insurance) => (Some(Vault::new(insurance.premium_resource)), Some(Vault::new(insurance.coverage_resource))),
+            None => (None, None),
+        };
+        let mut entry = Self {
+            escrow_nft_id,
+            requested_resources: Vec::new(),
+            // Storing the offered bundle with one vault per resource
+            offered_resources: vaults_of(offered_resources),
+            requested_resource_vaults: IndexMap::new(),
+            config,
+            filled_amounts: IndexMap::new(),
+            status: EscrowStatus::Open,
+            counter_offers: KeyValueStore::new(),
+            counter_offer_count: 0,
+            first_fill_epoch: None,
+            referral_vaults: IndexMap::new(),
+            payout_vaults: IndexMap::new(),
+            creator_vaults: IndexMap::new(),
+            engaged_counterparty: None,
+            taker_deposit: IndexMap::new(),
+            approval_deadline_epoch: None,
+            withdrawn_amounts: IndexMap::new(),
+            taker_badge,
+            taker_nft_id: None,
+            taker_receipt,
+            reputation_attestation,
+            settlement_claim,
+            claim_vaults: KeyValueStore::new(),
+            taker_fills: KeyValueStore::new(),
+            disputed: false,
+            refund_vaults: IndexMap::new(),
+            maker_bond: bond_resource.map(Vault::new),
+            taker_bond: bond_resource.map(Vault::new),
+            arbiter_bond_cut: bond_resource.map(Vault::new),
+            premium,
+            coverage,
+            underwritten_epoch: None,
+            coverage_triggered: false,
+            price_observations: Vec::new(),
+            dispute_votes: IndexMap::new(),
+            chosen_alternative: None,
+            paused: false,
+            recovery_requested_epoch: None,
+        };
+        entry.set_requested_resources(requested_resources);
+
+        Runtime::emit_event(EscrowInstantiated {
+            escrow_nft_id: entry.escrow_nft_id.clone(),
+            requested_resources: entry.requested_resources.clone(),
+            offered_resources: amounts_in(&entry.offered_resources),
+            expiry_epoch: entry.config.expiry_epoch,
+        });
+
+        entry
+    }
+
+    // Returns the offered resources together with any change left over from the payment,
+    // plus the protocol fee taken out of every fungible payment leg
+    pub fn exchange(
+        &mut self,
+        payment: Vec<Bucket>,
+        fee_bps: u16,
+        taker: TakerInput
+    ) -> (Vec<Bucket>, Vec<Bucket>, Vec<Bucket>) {
+        // Settled, cancelled and expired escrows must never take a taker's payment
+        self.assert_open();
+        assert!(
+            !self.config.two_sided && self.config.maker_approval_epochs.is_none(),
+            "Escrow settles deposited legs"
+        );
+        let referrer = taker.referrer;
+        let account = taker.account;
+        let max_price = taker.max_price;
+        let credential_id = self.assert_taker_allowed(taker);
+
+        // Takers of oracle-priced escrows can bound the price against the oracle moving after their quote
+        if let Some(max_price) = max_price {
+            assert!(self.config.oracle_pricing.is_some(), "Only oracle-priced escrows take a maximum price");
+            assert!(self.current_price().unwrap() <= max_price, "Oracle price moved above the maximum price");
+        }
+        self.assert_near_twap();
+
+        let payment = self.convert_payment(payment);
+        let paid_amounts = amounts_of(&payment);
+        let curve_quote = self.curve_quote();
+        let (partial_fill, fees, change) = self.collect_payment(payment, fee_bps);
+
+        // A partial fill only releases its proportional slice of the offered resources,
+        // or whatever it buys along the bonding curve
+        let mut offered: Vec<Bucket> = match partial_fill {
+            Some((fill_amount, remaining_amount)) => self.offered_resources
+                .values_mut()
+                .map(|vault| {
+                    let offered_amount = match &curve_quote {
+                        Some((bonding_curve, start_price, sold_amount)) => bonding_curve
+                            .amount_for(*start_price, *sold_amount, fill_amount)
+                            .min(vault.amount()),
+                        None => proportional_amount(vault.amount(), fill_amount, remaining_amount),
+                    };
+                    vault.take_advanced(offered_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .collect(),
+            None => {
+                self.status = EscrowStatus::Filled;
+                self.take_all_offered()
+            },
+        };
+        if partial_fill.is_some() {
+            self.mark_if_sold_out();
+        }
+
+        let paid = amounts_taken(paid_amounts, &change);
+        if let Some(per_taker_cap) = self.config.per_taker_cap {
+            let taker_id = match (&credential_id, account) {
+                (Some(credential_id), _) => TakerId::Credential(NonFungibleGlobalId::new(
+                    self.config.compliance_credential.unwrap(),
+                    credential_id.clone()
+                )),
+                (None, Some(account)) => TakerId::Account(account),
+                (None, None) => panic!("Capped escrows need a credential or an account to identify the taker"),
+            };
+            let paid_amount = paid.get(&self.requested_resources[0].get_resource_address()).copied().unwrap_or_default();
+            let taker_filled = self.taker_filled_amount(&taker_id) + paid_amount;
+            assert!(taker_filled <= per_taker_cap, "Fill exceeds the per-taker cap");
+            self.taker_fills.insert(taker_id, taker_filled);
+        }
+
+        let released = amounts_of(&offered);
+        if self.settlement_claim.is_some() {
+            offered = vec![self.hold_for_claim(offered, SettlementParty::Taker)];
+        }
+        Runtime::emit_event(EscrowFilled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            paid: paid.clone(),
+            released: released.clone(),
+            fees: amounts_of(&fees),
+            terms_hash: self.terms_hash(),
+            credential_id,
+        });
+
+        if let Some(referrer) = referrer.filter(|_| self.config.referral_fee_bps > 0) {
+            self.collect_referral_fee(referrer, &paid, &amounts_of(&fees));
+        }
+        if self.config.creator_royalty.is_some() {
+            self.collect_creator_royalty(&paid, &amounts_of(&fees));
+        }
+
+        if self.taker_receipt.is_some() {
+            offered.push(self.mint_taker_receipt(paid, released));
+        }
+
+        // The taker of an arbitrated escrow gets a badge to raise disputes and claim refunds with
+        if partial_fill.is_none() && self.config.arbiter_badge.is_some() {
+            offered.push(self.mint_taker_badge());
+        }
+        if partial_fill.is_none() && self.reputation_attestation.is_some() {
+            offered.push(self.mint_reputation_attestation(SettlementParty::Taker));
+        }
+
+        self.push_proceeds();
+
+        // Returns offered resources to the other party, along with whatever was left in their payment
+        (offered, change, fees)
+    }
+
+    // Settles this escrow against a complementary one. The offered resources pay the counterparty,
+    // and what the counterparty releases in turn has to fill this escrow completely.
+    // Returns whatever is left over on both sides.
+    pub fn swap(
+        &mut self,
+        counterparty_exchange: impl FnOnce(Vec<Bucket>) -> (Vec<Bucket>, Vec<Bucket>)
+    ) -> Vec<Bucket> {
+        self.assert_open();
+        assert!(
+            !self.config.two_sided && self.config.maker_approval_epochs.is_none(),
+            "Escrow settles deposited legs"
+        );
+        let credential_id = self.assert_taker_allowed(TakerInput::default());
+        assert!(self.config.arbiter_badge.is_none(), "Arbitrated escrows cannot be swapped");
+
+        let offered = self.take_all_offered();
+        let released_amounts = amounts_of(&offered);
+        let (counterparty_offered, mut surplus) = counterparty_exchange(offered);
+
+        let paid_amounts = amounts_of(&counterparty_offered);
+        let (partial_fill, _fees, change) = self.collect_payment(counterparty_offered, 0);
+        assert!(partial_fill.is_none(), "Swaps have to fill both escrows completely");
+        self.status = EscrowStatus::Filled;
+
+        Runtime::emit_event(EscrowFilled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            paid: amounts_taken(paid_amounts, &change),
+            released: released_amounts,
+            fees: IndexMap::new(),
+            terms_hash: self.terms_hash(),
+            credential_id,
+        });
+
+        surplus.extend(change);
+        surplus
+    }
+
+    // Holds the taker's leg of a two-sided escrow until settlement, returning the badge to back out with
+    // and whatever of the payment is not part of the leg
+    pub fn deposit_taker_leg(&mut self, payment: Vec<Bucket>, taker_account: ComponentAddress) -> (Bucket, Vec<Bucket>) {
+        assert!(self.config.two_sided, "Escrow is not two-sided");
+        self.hold_taker_leg(payment, taker_account)
+    }
+
+    // Holds a taker's payment until the instantiator approves them, returning the claim badge the approval
+    // refers to and whatever of the payment is not needed
+    pub fn request_approval(&mut self, payment: Vec<Bucket>, taker_account: ComponentAddress) -> (Bucket, Vec<Bucket>) {
+        let maker_approval_epochs = self.config.maker_approval_epochs.expect("Escrow does not require approval");
+        let (taker_nft, change) = self.hold_taker_leg(payment, taker_account);
+
+        let deadline_epoch = Runtime::current_epoch().number() + maker_approval_epochs;
+        self.approval_deadline_epoch = Some(deadline_epoch);
+        Runtime::emit_event(ApprovalRequested {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            taker_nft_id: taker_nft.as_non_fungible().non_fungible_local_id(),
+            deadline_epoch,
+        });
+
+        (taker_nft, change)
+    }
+
+    // Releases the offered resources to the approved taker, in exchange for their held payment
+    pub fn approve(&mut self, taker_claim: NonFungibleLocalId) {
+        assert!(self.config.maker_approval_epochs.is_some(), "Escrow does not require approval");
+        assert!(self.taker_nft_id.as_ref() == Some(&taker_claim), "Claim is not pending approval");
+        assert!(
+            self.approval_deadline_epoch.is_some_and(|deadline_epoch| Runtime::current_epoch().number() < deadline_epoch),
+            "Approval window has closed"
+        );
+        self.approval_deadline_epoch = None;
+
+        Runtime::emit_event(FillApproved {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            taker_nft_id: taker_claim,
+        });
+        self.settle_taker_leg();
+    }
+
+    // Returns a payment the instantiator has not approved in time to its taker, anyone can trigger it
+    pub fn refund_unapproved(&mut self) {
+        let deadline_epoch = self.approval_deadline_epoch.expect("No payment is pending approval");
+        assert!(Runtime::current_epoch().number() >= deadline_epoch, "Approval window is still open");
+        self.approval_deadline_epoch = None;
+        let taker_account = self.engaged_counterparty.take().unwrap();
+        let taker_nft_id = self.taker_nft_id.take().unwrap();
+
+        let returned = self.take_taker_deposit();
+        Runtime::emit_event(UnapprovedRefunded {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            taker_nft_id,
+            returned: amounts_of(&returned),
+        });
+
+        let mut taker_account: Global<Account> = taker_account.into();
+        taker_account.try_deposit_batch_or_abort(returned, None);
+    }
+
+    // Takes the requested resources out of a payment into the taker deposit, minting the taker a badge for it
+    fn hold_taker_leg(&mut self, payment: Vec<Bucket>, taker_account: ComponentAddress) -> (Bucket, Vec<Bucket>) {
+        self.assert_open();
+        self.assert_taker_allowed(TakerInput::default());
+        assert!(self.taker_deposit.values().all(|vault| vault.is_empty()), "Taker leg has already been deposited");
+
+        let mut payment_buckets = group_by_resource(payment);
+        let leg: Vec<Bucket> = self.requested_resources
+            .iter()
+            .map(|requested_resource| {
+                let bucket = payment_buckets
+                    .get_mut(&requested_resource.get_resource_address())
+                    .expect("Oooops missing requested resource");
+                match requested_resource {
+                    ResourceSpecifier::Fungible { amount, .. } => {
+                        assert!(bucket.amount() >= *amount, "Insufficient amount of resource");
+                        bucket.take(*amount)
+                    },
+                    ResourceSpecifier::NonFungible { non_fungible_local_ids, .. } => {
+                        bucket.as_non_fungible().take_non_fungibles(non_fungible_local_ids).into()
+                    },
+                    ResourceSpecifier::NonFungibleCount { count, .. } => {
+                        assert!(bucket.amount() >= Decimal::from(*count), "Insufficient amount of resource");
+                        bucket.take(Decimal::from(*count))
+                    },
+                }
+            })
+            .collect();
+
+        Runtime::emit_event(TakerLegDeposited {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            taker_account,
+            deposited: amounts_of(&leg),
+        });
+        for bucket in leg {
+            self.taker_deposit
+                .entry(bucket.resource_address())
+                .or_insert_with(|| Vault::new(bucket.resource_address()))
+                .put(bucket);
+        }
+        // Both legs are in, so backing out from now on costs the penalty
+        self.engaged_counterparty = Some(taker_account);
+
+        (self.mint_taker_badge(), payment_buckets.into_values().collect())
+    }
+
+    // Lets the taker of a two-sided escrow back out before settlement, forfeiting the penalty to the instantiator
+    pub fn withdraw_taker_leg(&mut self) -> Vec<Bucket> {
+        assert!(self.config.two_sided, "Escrow is not two-sided");
+        // Expired escrows are still open here, their taker has to be able to get the leg back
+        assert!(self.status == EscrowStatus::Open, "Escrow has already been settled or cancelled");
+        assert!(self.engaged_counterparty.take().is_some(), "No taker leg has been deposited");
+        self.taker_nft_id = None;
+
+        let mut returned = self.take_taker_deposit();
+        let penalty = self.take_penalty(&mut returned);
+        Runtime::emit_event(TakerLegWithdrawn {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            returned: amounts_of(&returned),
+            penalty: amounts_of(&penalty),
+        });
+        // The instantiator withdraws the penalty along with their proceeds
+        for bucket in penalty {
+            self.requested_resource_vaults[&bucket.resource_address()].put(bucket);
+        }
+
+        returned
+    }
+
+    // Swaps both deposited legs of a two-sided escrow, the offered resources go to the taker's account
+    pub fn settle(&mut self) {
+        assert!(self.config.two_sided, "Escrow is not two-sided");
+        self.settle_taker_leg();
+    }
+
+    fn settle_taker_leg(&mut self) {
+        self.assert_open();
+        let taker_account = self.engaged_counterparty.expect("No taker leg has been deposited");
+
+        let leg = self.take_taker_deposit();
+        let paid_amounts = amounts_of(&leg);
+        let (_partial_fill, _fees, change) = self.collect_payment(leg, 0);
+        self.status = EscrowStatus::Filled;
+
+        let offered = self.take_all_offered();
+        Runtime::emit_event(EscrowFilled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            paid: amounts_taken(paid_amounts, &change),
+            released: amounts_of(&offered),
+            fees: IndexMap::new(),
+            terms_hash: self.terms_hash(),
+            credential_id: None,
+        });
+
+        let mut taker_account: Global<Account> = taker_account.into();
+        taker_account.try_deposit_batch_or_abort(offered, None);
+    }
+
+    // Redeems a settlement claim for the resources it stands for
+    pub fn claim(&mut self, claim_nft: NonFungibleBucket) -> Vec<Bucket> {
+        assert!(
+            Some(claim_nft.resource_address()) == self.settlement_claim && claim_nft.amount() == Decimal::ONE,
+            "Invalid settlement claim"
+        );
+        let claim_nft_id = claim_nft.non_fungible_local_id();
+        let mut claim_vaults = self.claim_vaults.remove(&claim_nft_id).expect("Settlement claim not found");
+        claim_nft.burn();
+
+        let claimed: Vec<Bucket> = claim_vaults.values_mut().map(|vault| vault.take_all()).collect();
+        Runtime::emit_event(SettlementClaimed {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            claim_nft_id,
+            claimed: amounts_of(&claimed),
+        });
+
+        claimed
+    }
+
+    // Hands every referral fee collected for a referrer over to them
+    pub fn claim_referral_fees(&mut self, referrer: ResourceAddress) -> Vec<Bucket> {
+        self.referral_vaults
+            .get_mut(&referrer)
+            .expect("No referral fees collected for this referrer")
+            .values_mut()
+            .map(|vault| vault.take_all())
+            .collect()
+    }
+
+    pub fn claim_payout(&mut self, recipient: PayoutRecipient) -> Vec<Bucket> {
+        self.payout_vaults
+            .get_mut(&recipient)
+            .expect("No payout collected for this recipient")
+            .values_mut()
+            .map(|vault| vault.take_all())
+            .collect()
+    }
+
+    // Pays the recipients of the payout table their slices of the fungible proceeds on their way to the
+    // instantiator. Slices are rounded down, so the instantiator also keeps the remainder.
+    fn split_proceeds(&mut self, mut proceeds: Vec<Bucket>) -> Vec<Bucket> {
+        let amounts: Vec<Decimal> = proceeds.iter().map(|bucket| bucket.amount()).collect();
+        for (recipient, share_bps) in self.config.payout_split.clone() {
+            let payout: Vec<Bucket> = proceeds
+                .iter_mut()
+                .zip(&amounts)
+                .filter(|(bucket, _)| bucket.resource_address().is_fungible())
+                .map(|(bucket, amount)| {
+                    bucket.take_advanced(fee_amount(*amount, share_bps), WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .filter(|bucket| !bucket.is_empty())
+                .collect();
+            if payout.is_empty() {
+                continue;
+            }
+
+            Runtime::emit_event(ProceedsPaidOut {
+                escrow_nft_id: self.escrow_nft_id.clone(),
+                recipient: recipient.clone(),
+                payout: amounts_of(&payout),
+            });
+            // An account that refuses its payout cannot block the withdrawal, its payout is held for it instead
+            let held: Vec<Bucket> = match &recipient {
+                PayoutRecipient::Account(account) => {
+                    let mut account: Global<Account> = (*account).into();
+                    payout.into_iter().filter_map(|bucket| account.try_deposit_or_refund(bucket, None)).collect()
+                },
+                PayoutRecipient::Badge(_) => payout,
+            };
+            if held.is_empty() {
+                continue;
+            }
+            let payout_vaults = self.payout_vaults.entry(recipient).or_default();
+            for bucket in held {
+                payout_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket);
+            }
+        }
+        proceeds
+    }
+
+    // Moves the referrer's share of what was just paid into the fungible requested resource vaults
+    // out of the instantiator's proceeds
+    fn collect_referral_fee(
+        &mut self,
+        referrer: ResourceAddress,
+        paid: &IndexMap<ResourceAddress, Decimal>,
+        fees: &IndexMap<ResourceAddress, Decimal>
+    ) {
+        let referral_vaults = self.referral_vaults.entry(referrer).or_default();
+        let mut referral_fees = IndexMap::new();
+        for (resource_address, paid_amount) in paid.iter().filter(|(resource_address, _)| resource_address.is_fungible()) {
+            let proceeds = *paid_amount - fees.get(resource_address).copied().unwrap_or(Decimal::ZERO);
+            let referral_fee = self.requested_resource_vaults[resource_address].take_advanced(
+                fee_amount(proceeds, self.config.referral_fee_bps),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            referral_fees.insert(*resource_address, referral_fee.amount());
+            referral_vaults
+                .entry(*resource_address)
+                .or_insert_with(|| Vault::new(*resource_address))
+                .put(referral_fee);
+        }
+
+        Runtime::emit_event(ReferralFeeAccrued {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            referrer,
+            fees: referral_fees,
+        });
+    }
+
+    pub fn claim_creator_royalties(&mut self, creator_badge: ResourceAddress) -> Vec<Bucket> {
+        self.creator_vaults
+            .get_mut(&creator_badge)
+            .expect("No royalties collected for this creator")
+            .values_mut()
+            .map(|vault| vault.take_all())
+            .collect()
+    }
+
+    // Moves the royalty the creator of the offered NFTs currently asks for, capped by the instantiator, out of
+    // the instantiator's share of what was just paid into the fungible requested resource vaults
+    fn collect_creator_royalty(&mut self, paid: &IndexMap<ResourceAddress, Decimal>, fees: &IndexMap<ResourceAddress, Decimal>) {
+        let creator_royalty_policy = self.config.creator_royalty.clone().unwrap();
+        let offered_resource = *self.offered_resources.keys().next().unwrap();
+        let Some(creator_royalty) = creator_royalty_of(&creator_royalty_policy.source, offered_resource) else {
+            return;
+        };
+        let royalty_bps = creator_royalty.royalty_bps.min(creator_royalty_policy.max_royalty_bps);
+        if royalty_bps == 0 {
+            return;
+        }
+
+        let creator_vaults = self.creator_vaults.entry(creator_royalty.creator_badge).or_default();
+        let mut royalties = IndexMap::new();
+        for (resource_address, paid_amount) in paid.iter().filter(|(resource_address, _)| resource_address.is_fungible()) {
+            let proceeds = *paid_amount - fees.get(resource_address).copied().unwrap_or(Decimal::ZERO);
+            let royalty = self.requested_resource_vaults[resource_address].take_advanced(
+                fee_amount(proceeds, royalty_bps),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            royalties.insert(*resource_address, royalty.amount());
+            creator_vaults
+                .entry(*resource_address)
+                .or_insert_with(|| Vault::new(*resource_address))
+                .put(royalty);
+        }
+
+        Runtime::emit_event(CreatorRoyaltyAccrued {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            creator_badge: creator_royalty.creator_badge,
+            royalties,
+        });
+    }
+
+    // Pushes the proceeds of a fill to the instantiator's account locker, which sends them straight on to the
+    // account when it accepts them, or to the instantiator's account directly, keeping whatever it refuses.
+    // Proceeds on hold, vesting or settled by claim stay in the escrow instead.
+    fn push_proceeds(&mut self) {
+        if self.config.proceeds_locker.is_none() && self.config.maker_account.is_none() {
+            return;
+        }
+        if self.in_dispute_window() || self.config.vesting_epochs.is_some() || self.settlement_claim.is_some() {
+            return;
+        }
+
+        let proceeds: Vec<Bucket> = self.withdraw().into_iter().filter(|bucket| !bucket.is_empty()).collect();
+        if let Some(proceeds_locker) = self.config.proceeds_locker.clone() {
+            let mut locker: Global<AccountLocker> = proceeds_locker.locker.into();
+            let account: Global<Account> = proceeds_locker.account.into();
+            for bucket in proceeds {
+                locker.store(account, bucket, true);
+            }
+        } else if let Some(maker_account) = self.config.maker_account {
+            let mut account: Global<Account> = maker_account.into();
+            for bucket in proceeds {
+                if let Some(refused) = account.try_deposit_or_refund(bucket, None) {
+                    self.keep_refused(refused);
+                }
+            }
+        }
+    }
+
+    // Takes back proceeds the instantiator's account refused, for a later `withdraw_resource`
+    fn keep_refused(&mut self, refused: Bucket) {
+        let resource_address = refused.resource_address();
+        if let Some(withdrawn_amount) = self.withdrawn_amounts.get_mut(&resource_address) {
+            *withdrawn_amount -= refused.amount();
+        }
+        self.requested_resource_vaults
+            .entry(resource_address)
+            .or_insert_with(|| Vault::new(resource_address))
+            .put(refused);
+        if self.status == EscrowStatus::Withdrawn {
+            self.status = EscrowStatus::Filled;
+        }
+    }
+
+    // Swaps every payment bucket in an accepted input token into the requested token through the configured DEX.
+    // A swap returning another resource or less than the minimum rate fails the whole fill.
+    fn convert_payment(&self, payment: Vec<Bucket>) -> Vec<Bucket> {
+        let Some(dex_conversion) = &self.config.dex_conversion else {
+            return payment;
+        };
+        let requested_resource = self.requested_resources[0].get_resource_address();
+        let dex: Global<AnyComponent> = dex_conversion.dex.into();
+
+        let mut converted = Vec::new();
+        for bucket in payment {
+            let input_resource = bucket.resource_address();
+            let Some(min_rate) = dex_conversion.min_rates.get(&input_resource) else {
+                converted.push(bucket);
+                continue;
+            };
+            let mut swapped_amount = bucket.amount();
+            let output = match dex_conversion.swap_output {
+                SwapOutput::Bucket => dex.call::<_, Bucket>(&dex_conversion.swap_method, &(bucket,)),
+                SwapOutput::BucketAndRemainder => {
+                    // The remainder goes back to the taker as change
+                    let (output, remainder): (Bucket, Bucket) = dex.call(&dex_conversion.swap_method, &(bucket,));
+                    assert!(remainder.resource_address() == input_resource, "DEX returned an unexpected remainder");
+                    swapped_amount -= remainder.amount();
+                    converted.push(remainder);
+                    output
+                },
+            };
+            assert!(output.resource_address() == requested_resource, "DEX returned another resource than requested");
+            assert!(output.amount() >= swapped_amount * *min_rate, "DEX swap slipped below the minimum rate");
+
+            Runtime::emit_event(PaymentConverted {
+                escrow_nft_id: self.escrow_nft_id.clone(),
+                input_resource,
+                input_amount: swapped_amount,
+                output_amount: output.amount(),
+            });
+            converted.push(output);
+        }
+        converted
+    }
+
+    // Validates a payment against every requested leg and moves what is owed into the requested resource vaults.
+    // Returns the partial fill if there is one, the protocol fees and whatever is left of the payment.
+    fn collect_payment(
+        &mut self,
+        payment: Vec<Bucket>,
+        fee_bps: u16
+    ) -> (Option<(Decimal, Decimal)>, Vec<Bucket>, Vec<Bucket>) {
+        // Grouping the payment by resource so every requested leg can be matched against it
+        let mut payment_buckets = group_by_resource(payment);
+
+        // Dutch auctions and oracle-priced escrows ask for their current price instead of the requested amount
+        let current_price = self.current_price();
+        let requested_resources = self.payment_terms(&payment_buckets);
+        let unfilled_fraction = (!self.config.currency_prices.is_empty()).then(|| self.unfilled_fraction());
+
+        // Every requested leg is validated and collected before anything is released.
+        // A partial fill keeps track of the filled and remaining amount of the single fungible leg.
+        let mut partial_fill: Option<(Decimal, Decimal)> = None;
+        let mut fees: Vec<Bucket> = Vec::new();
+        for requested_resource in &requested_resources {
+            let resource_address = requested_resource.get_resource_address();
+            // Provided resources need to include every requested resource address
+            let bucket_of_resource = payment_buckets
+                .get_mut(&resource_address)
+                .expect("Oooops missing requested resource");
+            // Alternatives only get their vault once a taker pays with them
+            let requested_resource_vault = self.requested_resource_vaults
+                .entry(resource_address)
+                .or_insert_with(|| Vault::new(resource_address));
+
+            match requested_resource {
+                ResourceSpecifier::Fungible { amount, .. } => {
+                    if let Some(validator) = self.config.lsu_validator {
+                        assert_liquid_stake_unit(validator, resource_address);
+                    }
+                    let amount = current_price.unwrap_or(*amount);
+                    let filled_amount = self.filled_amounts.entry(resource_address).or_insert(Decimal::ZERO);
+                    // Multi-currency escrows share what is left of the offer between all accepted currencies
+                    let remaining_amount = match unfilled_fraction {
+                        Some(unfilled_fraction) => amount * unfilled_fraction,
+                        None => amount - *filled_amount,
+                    };
+                    assert!(remaining_amount > Decimal::ZERO, "Escrow is already filled");
+
+                    // Provided resource need to match the requested amount, unless partial fills are allowed
+                    if !self.config.allow_partial_fills {
+                        assert!(bucket_of_resource.amount() >= remaining_amount, "Insufficient amount of resource");
+                    }
+                    // Fill-or-kill escrows take what is left in one go, or not at all
+                    if self.config.fill_or_kill {
+                        assert!(bucket_of_resource.amount() >= remaining_amount, "Fill-or-kill escrows have to be filled completely");
+                    }
+                    // Sale-capped escrows stop collecting at the cap, whatever is left of the offer
+                    let fillable_amount = match self.config.sale_cap {
+                        Some(sale_cap) => remaining_amount.min(sale_cap - *filled_amount),
+                        None => remaining_amount,
+                    };
+                    let fill_amount = bucket_of_resource.amount().min(fillable_amount);
+                    assert!(fill_amount > Decimal::ZERO, "Empty payment bucket");
+                    // Dust fills are turned away, except for the one taking whatever is left
+                    if let Some(min_fill_amount) = self.config.min_fill_amount {
+                        assert!(
+                            fill_amount >= min_fill_amount || fill_amount == fillable_amount,
+                            "Fill is below the minimum fill amount"
+                        );
+                    }
+                    if let Some(pool_units) = &self.config.pool_units {
+                        assert!(
+                            pool_redemption_value(pool_units.pool, pool_units.underlying, fill_amount)
+                                >= pool_units.min_value_per_unit * fill_amount,
+                            "Pool units redeem for less than the minimum underlying value"
+                        );
+                    }
+
+                    // Transfer the filled amount to the requested resource vault, minus the protocol fee.
+                    // The fee comes out of the instantiator's proceeds, the taker still pays the requested amount.
+                    let mut filled = bucket_of_resource.take(fill_amount);
+                    if fee_bps > 0 {
+                        let fee_amount = fee_amount(fill_amount, fee_bps);
+                        fees.push(filled.take_advanced(fee_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero)));
+                    }
+                    requested_resource_vault.put(filled);
+                    *filled_amount += fill_amount;
+
+                    if fill_amount < remaining_amount {
+                        partial_fill = Some((fill_amount, remaining_amount));
+                    }
+                },
+                ResourceSpecifier::NonFungible { non_fungible_local_ids, .. } => {
+                    // Provided resource matches every requested resource ID
+                    assert!(
+                        non_fungible_local_ids
+                            .iter()
+                            .all(|non_fungible_local_id| bucket_of_resource.as_non_fungible().contains_non_fungible(non_fungible_local_id)),
+                        "Non-fungible ID not found"
+                    );
+
+                    // Transfer the requested non-fungible tokens to the requested resource vault.
+                    let requested_non_fungibles = bucket_of_resource.as_non_fungible().take_non_fungibles(non_fungible_local_ids);
+                    requested_resource_vault.put(requested_non_fungibles.into());
+                },
+                ResourceSpecifier::NonFungibleCount { count, .. } => {
+                    // Any non-fungibles of the collection will do, as long as there are enough of them
+                    let count = Decimal::from(*count);
+                    assert!(bucket_of_resource.amount() >= count, "Insufficient amount of resource");
+
+                    // Transfer the requested number of non-fungible tokens to the requested resource vault.
+                    let non_fungibles = bucket_of_resource.take(count);
+                    if let Some(unstake_claims) = &self.config.unstake_claims {
+                        unstake_claims.assert_accepts(&non_fungibles);
+                    }
+                    requested_resource_vault.put(non_fungibles);
+                },
+            }
+        }
+
+        // Exact matching leaves no change, so the taker has paid precisely what was requested
+        if self.config.matching_mode == MatchingMode::Exact {
+            assert!(
+                payment_buckets.values().all(|bucket| bucket.is_empty()),
+                "Payment has to match the requested resources exactly"
+            );
+        }
+
+        // Vesting of the proceeds starts with the first fill
+        self.first_fill_epoch.get_or_insert(Runtime::current_epoch().number());
+
+        // The change is still sitting in the payment buckets
+        (partial_fill, fees, payment_buckets.into_values().collect())
+    }
+
+    // Share of the offer that is still unfilled, with every accepted currency filling it at its own price
+    fn unfilled_fraction(&self) -> Decimal {
+        let mut prices = self.config.currency_prices.clone();
+        if let [ResourceSpecifier::Fungible { resource_address, amount }] = self.requested_resources.as_slice() {
+            prices.insert(*resource_address, *amount);
+        }
+
+        unfilled_fraction(prices.iter().map(|(resource_address, price)| {
+            (*price, self.filled_amounts.get(resource_address).copied().unwrap_or(Decimal::ZERO))
+        }))
+    }
+
+    // Terms a payment is settled on. Either-or escrows use their requested resources when the payment
+    // covers all of them, and otherwise the first alternative the payment satisfies, which is recorded.
+    fn payment_terms(&mut self, payment_buckets: &IndexMap<ResourceAddress, Bucket>) -> Vec<ResourceSpecifier> {
+        let covers_requested = self.requested_resources
+            .iter()
+            .all(|requested_resource| payment_buckets.contains_key(&requested_resource.get_resource_address()));
+        if covers_requested {
+            return self.requested_resources.clone();
+        }
+
+        // Multi-currency escrows are paid in the first accepted currency found in the payment
+        if let Some((resource_address, price)) = self.config.currency_prices
+            .iter()
+            .find(|(resource_address, _)| payment_buckets.contains_key(*resource_address))
+        {
+            return vec![ResourceSpecifier::Fungible { resource_address: *resource_address, amount: *price }];
+        }
+        if self.config.alternative_resources.is_empty() {
+            return self.requested_resources.clone();
+        }
+
+        let alternative = self.config.alternative_resources
+            .iter()
+            .find(|alternative| {
+                payment_buckets
+                    .get(&alternative.get_resource_address())
+                    .is_some_and(|bucket| alternative.is_satisfied_by(bucket))
+            })
+            .cloned()
+            .expect("Payment does not satisfy the requested resources or any alternative");
+        self.chosen_alternative = Some(alternative.clone());
+
+        Runtime::emit_event(AlternativeChosen {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            requested_resource: alternative.clone(),
+        });
+
+        vec![alternative]
+    }
+
+    // Hands the collected requested resources over to the instantiator
+    pub fn withdraw(&mut self) -> Vec<Bucket> {
+        // Partially filled escrows can be withdrawn from while they are still open or after a cancellation
+        assert!(
+            matches!(
+                self.status,
+                EscrowStatus::Open | EscrowStatus::Filled | EscrowStatus::Cancelled | EscrowStatus::SoldOut
+            ),
+            "Escrow has nothing left to withdraw"
+        );
+        // The payment stays on hold while the taker can still dispute the fill
+        assert!(!self.in_dispute_window(), "Payment is on hold during the dispute window");
+
+        // Returns the vested share of the requested resources to the instantiator
+        let vested_fraction = self.vested_fraction();
+        let proceeds: Vec<Bucket> = self.requested_resource_vaults
+            .iter_mut()
+            .map(|(resource_address, vault)| {
+                let withdrawn_amount = self.withdrawn_amounts.entry(*resource_address).or_insert(Decimal::ZERO);
+                let unlocked_amount = unlocked_amount(vault.amount(), *withdrawn_amount, vested_fraction);
+                let bucket = vault.take_advanced(unlocked_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+                *withdrawn_amount += bucket.amount();
+                bucket
+            })
+            .collect();
+        let mut withdrawn = self.split_proceeds(proceeds);
+
+        // What a sold-out escrow did not sell goes back to the instantiator with the proceeds
+        if self.status == EscrowStatus::SoldOut {
+            withdrawn.extend(self.take_all_offered());
+        }
+
+        // A filled escrow is done once every vested resource has been withdrawn
+        let settled = matches!(self.status, EscrowStatus::Filled | EscrowStatus::SoldOut)
+            && self.requested_resource_vaults.values().all(|vault| vault.is_empty());
+        if settled {
+            self.status = EscrowStatus::Withdrawn;
+        }
+
+        Runtime::emit_event(EscrowWithdrawn {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            withdrawn: amounts_of(&withdrawn),
+        });
+
+        if self.settlement_claim.is_some() {
+            withdrawn = vec![self.hold_for_claim(withdrawn, SettlementParty::Maker)];
+        }
+
+        // The instantiator's attestation comes with the final withdrawal
+        if settled && self.reputation_attestation.is_some() {
+            withdrawn.push(self.mint_reputation_attestation(SettlementParty::Maker));
+        }
+
+        withdrawn
+    }
+
+    // Deposits more of an already offered fungible resource into an open escrow
+    pub fn add_offered(&mut self, bucket: Bucket) {
+        self.assert_open();
+        assert!(bucket.resource_address().is_fungible(), "Only fungible offered resources can be topped up");
+        assert!(!bucket.is_empty(), "Cannot add an empty bucket");
+
+        let resource_address = bucket.resource_address();
+        let amount = bucket.amount();
+        self.offered_resources
+            .get_mut(&resource_address)
+            .expect("Resource is not part of the offer")
+            .put(bucket);
+
+        Runtime::emit_event(OfferedAdded {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            resource_address,
+            amount,
+        });
+    }
+
+    // Blocks or unblocks fills. Withdrawing and cancelling stay available to the instantiator while paused.
+    pub fn set_paused(&mut self, paused: bool) {
+        assert!(self.paused != paused, "Escrow is already {}", if paused { "paused" } else { "unpaused" });
+        self.paused = paused;
+
+        let escrow_nft_id = self.escrow_nft_id.clone();
+        if paused {
+            Runtime::emit_event(EscrowPaused { escrow_nft_id });
+        } else {
+            Runtime::emit_event(EscrowUnpaused { escrow_nft_id });
+        }
+    }
+
+    // Adds a taker to, or removes one from, the allowlist of the escrow
+    pub fn set_taker_allowed(&mut self, taker: ComponentAddress, allowed: bool) {
+        let taker_allowlist = self.config.taker_allowlist.as_mut().expect("Escrow has no taker allowlist");
+        if allowed {
+            assert!(taker_allowlist.insert(taker), "Taker is already on the allowlist");
+        } else {
+            assert!(taker_allowlist.shift_remove(&taker), "Taker is not on the allowlist");
+        }
+
+        Runtime::emit_event(TakerAllowlistUpdated {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            taker,
+            allowed,
+        });
+    }
+
+    // Binds the escrow to a freshly minted EscrowBadge, which replaces the burnt one
+    pub fn reissue_badge(&mut self, escrow_nft_id: NonFungibleLocalId) {
+        Runtime::emit_event(EscrowOwnershipTransferred {
+            previous_escrow_nft_id: self.escrow_nft_id.clone(),
+            escrow_nft_id: escrow_nft_id.clone(),
+        });
+
+        self.escrow_nft_id = escrow_nft_id;
+    }
+
+    // Starts the delay after which the recovery authority can recall a lost EscrowBadge
+    pub fn request_badge_recovery(&mut self, authority: Proof) {
+        let badge_recovery = self.verify_recovery_authority(authority);
+        let recoverable_epoch = Runtime::current_epoch().number() + badge_recovery.delay_epochs;
+        self.recovery_requested_epoch = Some(Runtime::current_epoch().number());
+
+        Runtime::emit_event(BadgeRecoveryRequested {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            recoverable_epoch,
+        });
+    }
+
+    // Lets an instantiator who still holds the badge stop a recovery during the delay
+    pub fn cancel_badge_recovery(&mut self) {
+        assert!(self.recovery_requested_epoch.take().is_some(), "No badge recovery has been requested");
+
+        Runtime::emit_event(BadgeRecoveryCancelled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+        });
+    }
+
+    // Checks that the recovery delay has passed, ahead of the badge being recalled
+    pub fn complete_badge_recovery(&mut self, authority: Proof) {
+        let badge_recovery = self.verify_recovery_authority(authority);
+        let requested_epoch = self.recovery_requested_epoch.expect("No badge recovery has been requested");
+        assert!(
+            Runtime::current_epoch().number() >= requested_epoch + badge_recovery.delay_epochs,
+            "Badge recovery is still delayed"
+        );
+        self.recovery_requested_epoch = None;
+
+        Runtime::emit_event(BadgeRecovered {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+        });
+    }
+
+    // Retires part of the unfilled requested amount, handing back the matching slice of the offered resources.
+    // Retiring everything that is left closes the escrow.
+    pub fn withdraw_unsold(&mut self, retired_amount: Decimal) -> Vec<Bucket> {
+        assert!(self.config.allow_partial_fills, "Only partially fillable escrows can withdraw unsold resources");
+        assert!(self.config.currency_prices.is_empty(), "Multi-currency escrows cannot withdraw unsold resources");
+        assert!(self.config.bonding_curve.is_none(), "Bonding-curve escrows cannot withdraw unsold resources");
+        self.assert_open();
+
+        // Partially fillable escrows have a single fungible requested leg
+        let (resource_address, amount) = match self.requested_resources.as_slice() {
+            [ResourceSpecifier::Fungible { resource_address, amount }] => (*resource_address, *amount),
+            _ => unreachable!(),
+        };
+        let remaining_amount = self.remaining_amount(resource_address);
+        assert!(
+            retired_amount > Decimal::ZERO && retired_amount <= remaining_amount,
+            "Retired amount must be positive and at most the remaining amount"
+        );
+
+        let returned: Vec<Bucket> = if retired_amount == remaining_amount {
+            self.status = EscrowStatus::Cancelled;
+            self.take_all_offered()
+        } else {
+            self.offered_resources
+                .values_mut()
+                .map(|vault| {
+                    let returned_amount = proportional_amount(vault.amount(), retired_amount, remaining_amount);
+                    vault.take_advanced(returned_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .collect()
+        };
+        // Per-unit escrows keep their unit price, the others ask that much less in total
+        if !self.config.per_unit_pricing {
+            self.requested_resources = vec![ResourceSpecifier::Fungible {
+                resource_address,
+                amount: amount - retired_amount,
+            }];
+        }
+
+        Runtime::emit_event(UnsoldWithdrawn {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            retired_amount,
+            returned: amounts_of(&returned),
+        });
+
+        returned
+    }
+
+    // Sends the offered resources of an expired hash-locked escrow back to its refund account
+    pub fn refund_expired(&mut self) {
+        let hash_lock = self.config.hash_lock.clone().expect("Escrow is not hash-locked");
+        assert!(self.status() == EscrowStatus::Expired, "Escrow has not expired");
+        self.status = EscrowStatus::Cancelled;
+
+        let returned = self.take_all_offered();
+        Runtime::emit_event(EscrowCancelled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            returned: amounts_of(&returned),
+        });
+
+        let mut refund_account: Global<Account> = hash_lock.refund_account.into();
+        refund_account.try_deposit_batch_or_abort(returned, None);
+    }
+
+    // Pays the offered resources of an escrow left expired for the grace period out to its beneficiary
+    pub fn pay_beneficiary(&mut self) {
+        let beneficiary = self.config.beneficiary.clone().expect("Escrow has no beneficiary");
+        assert!(self.status() == EscrowStatus::Expired, "Escrow has not expired");
+        assert!(
+            Runtime::current_epoch().number() >= self.config.expiry_epoch.unwrap() + beneficiary.grace_epochs,
+            "Grace period has not passed"
+        );
+        self.status = EscrowStatus::Cancelled;
+
+        let paid = self.take_all_offered();
+        Runtime::emit_event(BeneficiaryPaid {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            beneficiary: beneficiary.account,
+            paid: amounts_of(&paid),
+        });
+
+        let mut beneficiary_account: Global<Account> = beneficiary.account.into();
+        beneficiary_account.try_deposit_batch_or_abort(paid, None);
+    }
+
+    // Returns the offered resources of an expired escrow to the instantiator's destination,
+    // minus the bounty handed to whoever triggered it
+    pub fn reclaim_expired(&mut self) -> Vec<Bucket> {
+        let expiry_reclaim = self.config.expiry_reclaim.clone().expect("Escrow cannot be reclaimed");
+        assert!(self.status() == EscrowStatus::Expired, "Escrow has not expired");
+        assert!(
+            Runtime::current_epoch().number() >= self.config.expiry_epoch.unwrap() + expiry_reclaim.grace_epochs,
+            "Escrow is still in its grace period"
+        );
+        self.status = EscrowStatus::Cancelled;
+
+        let mut returned = self.take_all_offered();
+        let bounty: Vec<Bucket> = returned
+            .iter_mut()
+            .filter(|bucket| bucket.resource_address().is_fungible())
+            .map(|bucket| {
+                let bounty_amount = fee_amount(bucket.amount(), expiry_reclaim.bounty_bps);
+                bucket.take_advanced(bounty_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+            })
+            .collect();
+        Runtime::emit_event(ExpiredReclaimed {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            returned: amounts_of(&returned),
+            bounty: amounts_of(&bounty),
+        });
+
+        let mut destination: Global<Account> = expiry_reclaim.destination.into();
+        destination.try_deposit_batch_or_abort(returned, None);
+
+        bounty
+    }
+
+    // Moves the deadline of an escrow that is still open further out. An expired escrow stays expired,
+    // so its beneficiary or a keeper can count on what expiry entitles them to.
+    pub fn extend_deadline(&mut self, deadline: Deadline) {
+        assert!(self.status() == EscrowStatus::Open, "Only open escrows can have their deadline extended");
+        // The counterparty of an atomic swap relies on the lock running out when agreed
+        assert!(self.config.hash_lock.is_none(), "Hash-locked escrows cannot extend their deadline");
+
+        match deadline {
+            Deadline::Epoch(expiry_epoch) => {
+                let current_expiry_epoch = self.config.expiry_epoch.expect("Escrow has no expiry epoch");
+                assert!(
+                    expiry_epoch > current_expiry_epoch && expiry_epoch > Runtime::current_epoch().number(),
+                    "Deadline can only be moved to a later epoch in the future"
+                );
+                self.config.expiry_epoch = Some(expiry_epoch);
+            },
+            Deadline::Time(instant) => {
+                let current_expiry_time = self.config.expiry_time.expect("Escrow has no expiry time");
+                let expiry_time = ClockDeadline { instant, precision: current_expiry_time.precision };
+                assert!(
+                    instant > current_expiry_time.instant && !expiry_time.has_passed(),
+                    "Deadline can only be moved to a later time in the future"
+                );
+                self.config.expiry_time = Some(expiry_time);
+            },
+        }
+
+        Runtime::emit_event(DeadlineExtended {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            deadline,
+        });
+    }
+
+    pub(crate) fn expiry_epoch(&self) -> Option<u64> {
+        self.config.expiry_epoch
+    }
+
+    // Winds the escrow up for good in one go, handing back whatever is left of the offer along with
+    // everything collected. The caller burns the badge, so nothing can be done with the escrow afterwards.
+    pub fn close(&mut self) -> Vec<Bucket> {
+        assert!(
+            matches!(
+                self.status(),
+                EscrowStatus::Open | EscrowStatus::Filled | EscrowStatus::Cancelled | EscrowStatus::SoldOut | EscrowStatus::Expired
+            ),
+            "Escrow cannot be closed"
+        );
+        assert!(!self.in_dispute_window(), "Payment is on hold during the dispute window");
+        assert!(self.vested_fraction() == Decimal::ONE, "Payment is still vesting");
+        assert!(self.taker_deposit.values().all(|vault| vault.is_empty()), "A taker's deposit is still held");
+
+        // Open escrows are cancelled first, so a committed counterparty still gets their penalty
+        let mut returned = if self.status == EscrowStatus::Open { self.cancel() } else { self.take_all_offered() };
+        let proceeds: Vec<Bucket> = self.requested_resource_vaults.values_mut().map(|vault| vault.take_all()).collect();
+        let withdrawn = self.split_proceeds(proceeds);
+        self.status = EscrowStatus::Closed;
+
+        Runtime::emit_event(EscrowClosed {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            returned: amounts_of(&returned),
+            withdrawn: amounts_of(&withdrawn),
+        });
+
+        returned.extend(withdrawn);
+        if self.settlement_claim.is_some() {
+            returned = vec![self.hold_for_claim(returned, SettlementParty::Maker)];
+        }
+        returned
+    }
+
+    // Checks that the escrow can be handed over to maker shares. Nobody manages it afterwards, so it has to end
+    // on its own, pay out nowhere else than to the share holders, and hold only resources that can be split.
+    pub fn assert_fractionalizable(&self) {
+        assert!(self.status() == EscrowStatus::Open, "Only open escrows can be fractionalized");
+        assert!(
+            self.config.expiry_epoch.is_some() || self.config.expiry_time.is_some(),
+            "Fractionalized escrows need a deadline"
+        );
+        assert!(
+            self.offered_resources.keys().all(|resource_address| resource_address.is_fungible())
+                && self.requested_resources
+                    .iter()
+                    .chain(&self.config.alternative_resources)
+                    .all(|specifier| matches!(specifier, ResourceSpecifier::Fungible { .. })),
+            "Only fungible escrows can be fractionalized"
+        );
+        assert!(
+            self.config.hash_lock.is_none()
+                && self.config.beneficiary.is_none()
+                && self.config.expiry_reclaim.is_none()
+                && self.config.proceeds_locker.is_none()
+                && self.config.maker_account.is_none()
+                && self.config.payout_split.is_empty()
+                && !self.config.claim_settlement,
+            "Escrow pays out elsewhere than to the share holders"
+        );
+    }
+
+    pub fn cancel(&mut self) -> Vec<Bucket> {
+        assert!(self.status == EscrowStatus::Open, "Only open escrows can be cancelled");
+        // The status indicates that the escrow is canceled, the badge is kept so any
+        // payment collected from partial fills can still be withdrawn
+        self.status = EscrowStatus::Cancelled;
+
+        // Return the offered resources to the instantiator
+        // This ensures that the instantiator gets back their resources
+        let mut returned = self.take_all_offered();
+
+        // Backing out of a committed deal costs the instantiator the penalty
+        if let Some(counterparty) = self.engaged_counterparty.take() {
+            self.approval_deadline_epoch = None;
+            let mut penalty = self.take_penalty(&mut returned);
+            if !penalty.is_empty() {
+                Runtime::emit_event(CancellationPenaltyPaid {
+                    escrow_nft_id: self.escrow_nft_id.clone(),
+                    counterparty,
+                    penalty: amounts_of(&penalty),
+                });
+            }
+
+            // The taker of a two-sided escrow gets their leg back along with the penalty
+            penalty.extend(self.take_taker_deposit());
+            let mut counterparty_account: Global<Account> = counterparty.into();
+            counterparty_account.try_deposit_batch_or_abort(penalty, None);
+        }
+
+        Runtime::emit_event(EscrowCancelled {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            returned: amounts_of(&returned),
+        });
+
+        returned
+    }
+
+    // Puts the collected payment on hold until the arbiter resolves the dispute
+    pub fn raise_dispute(&mut self) {
+        assert!(self.config.arbiter_badge.is_some(), "Escrow has no arbiter");
+        assert!(self.status == EscrowStatus::Filled, "Only filled escrows can be disputed");
+        assert!(!self.disputed, "Escrow has already been disputed");
+        if self.config.dispute_window_epochs.is_some() {
+            assert!(self.in_dispute_window(), "Dispute window has closed");
+        }
+        self.status = EscrowStatus::Disputed;
+        self.disputed = true;
+
+        Runtime::emit_event(DisputeRaised {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+        });
+    }
+
+    // Records the verdict of an arbiter, applying it once enough arbiters of the council agree on it
+    pub fn resolve_dispute(&mut self, arbiter: Proof, award: DisputeAward) {
+        assert!(self.status == EscrowStatus::Disputed, "Escrow is not in dispute");
+        let arbiter_badge = self.config.arbiter_badge.expect("Escrow has no arbiter");
+        let arbiter = arbiter.check_with_message(arbiter_badge, "Invalid arbiter badge");
+
+        if self.config.arbiter_threshold <= 1 {
+            self.apply_verdict(award);
+            return;
+        }
+
+        // Every arbiter of the council holds one badge of the arbiter resource and has a single vote
+        let arbiter_nft_id = arbiter.as_non_fungible().non_fungible_local_id();
+        self.dispute_votes.insert(arbiter_nft_id.clone(), award);
+        Runtime::emit_event(DisputeVoteCast {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            arbiter_nft_id,
+            award,
+        });
+
+        let votes = self.dispute_votes.values().filter(|vote| **vote == award).count();
+        if votes >= self.config.arbiter_threshold as usize {
+            self.apply_verdict(award);
+        }
+    }
+
+    // Hands the share of the collected payment awarded to the taker over to them
+    pub fn claim_refund(&mut self) -> Vec<Bucket> {
+        assert!(self.refund_vaults.values().any(|vault| !vault.is_empty()), "Nothing to refund");
+        self.refund_vaults.values_mut().map(|vault| vault.take_all()).collect()
+    }
+
+    // Holds the collateral bond of one side, which has to post exactly the configured amount
+    pub fn post_bond(&mut self, party: SettlementParty, bond: Bucket) {
+        let collateral_bond = self.config.collateral_bond.clone().expect("Escrow takes no collateral bonds");
+        assert!(
+            matches!(self.status, EscrowStatus::Open | EscrowStatus::Filled),
+            "Bonds can only back an escrow that can still be disputed"
+        );
+        assert!(
+            bond.resource_address() == collateral_bond.resource_address && bond.amount() == collateral_bond.amount,
+            "Bond has to match the configured collateral"
+        );
+        let bond_vault = self.bond_vault(party);
+        assert!(bond_vault.is_empty(), "Bond has already been posted");
+        bond_vault.put(bond);
+
+        Runtime::emit_event(BondPosted {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            party,
+            amount: collateral_bond.amount,
+        });
+    }
+
+    // Hands a side its bond back, along with whatever it won slashing the other side's,
+    // once the escrow can no longer be disputed
+    pub fn withdraw_bond(&mut self, party: SettlementParty) -> Bucket {
+        assert!(!self.bonds_locked(), "Bonds are locked while the escrow can be disputed");
+        let bond = self.bond_vault(party).take_all();
+
+        Runtime::emit_event(BondWithdrawn {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            party,
+            amount: bond.amount(),
+        });
+
+        bond
+    }
+
+    // Hands the arbiter's cut of slashed bonds over to a holder of the arbiter badge
+    pub fn claim_arbiter_bond_cut(&mut self, arbiter: Proof) -> Bucket {
+        let arbiter_badge = self.config.arbiter_badge.expect("Escrow has no arbiter");
+        arbiter.check_with_message(arbiter_badge, "Invalid arbiter badge");
+        self.arbiter_bond_cut.as_mut().expect("Escrow takes no collateral bonds").take_all()
+    }
+
+    // Holds the premium of the insured party until an insurer underwrites the coverage
+    pub fn pay_premium(&mut self, party: SettlementParty, premium: Bucket) {
+        let insurance = self.insurance_of(party);
+        assert!(self.underwritten_epoch.is_none(), "Coverage has already been underwritten");
+        assert!(
+            premium.resource_address() == insurance.premium_resource && premium.amount() == insurance.premium_amount,
+            "Premium has to match the insurance terms"
+        );
+        let premium_vault = self.premium.as_mut().unwrap();
+        assert!(premium_vault.is_empty(), "Premium has already been paid");
+        premium_vault.put(premium);
+
+        Runtime::emit_event(PremiumPaid {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            amount: insurance.premium_amount,
+        });
+    }
+
+    // Returns the premium to the insured party as long as no insurer has underwritten the coverage
+    pub fn withdraw_premium(&mut self, party: SettlementParty) -> Bucket {
+        self.insurance_of(party);
+        assert!(self.underwritten_epoch.is_none(), "Coverage has already been underwritten");
+        self.premium.as_mut().unwrap().take_all()
+    }
+
+    // Deposits the coverage against the escrow, starting the coverage window, and pays the insurer the premium
+    pub fn underwrite(&mut self, insurer: Proof, coverage: Bucket) -> Bucket {
+        let insurance = self.config.insurance.clone().expect("Escrow is not insurable");
+        insurer.check_with_message(insurance.insurer_badge, "Invalid insurer badge");
+        assert!(self.underwritten_epoch.is_none(), "Coverage has already been underwritten");
+        assert!(!self.premium.as_ref().unwrap().is_empty(), "Premium has not been paid");
+        assert!(self.status != EscrowStatus::Disputed, "Disputed escrows cannot be insured");
+        assert!(
+            coverage.resource_address() == insurance.coverage_resource && coverage.amount() == insurance.coverage_amount,
+            "Coverage has to match the insurance terms"
+        );
+        self.coverage.as_mut().unwrap().put(coverage);
+
+        let underwritten_epoch = Runtime::current_epoch().number();
+        self.underwritten_epoch = Some(underwritten_epoch);
+        Runtime::emit_event(CoverageUnderwritten {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            coverage: insurance.coverage_amount,
+            end_epoch: underwritten_epoch + insurance.coverage_epochs,
+        });
+
+        self.premium.as_mut().unwrap().take_all()
+    }
+
+    // Hands the coverage to the insured party once a verdict has gone against them
+    pub fn claim_coverage(&mut self, party: SettlementParty) -> Bucket {
+        self.insurance_of(party);
+        assert!(self.coverage_triggered, "Coverage has not been triggered");
+        self.coverage.as_mut().unwrap().take_all()
+    }
+
+    // Returns the coverage to the insurer once it can no longer be triggered
+    pub fn withdraw_coverage(&mut self, insurer: Proof) -> Bucket {
+        let insurance = self.config.insurance.clone().expect("Escrow is not insurable");
+        insurer.check_with_message(insurance.insurer_badge, "Invalid insurer badge");
+        assert!(!self.coverage_triggered, "Coverage has been paid out");
+
+        let coverage_ended = self.underwritten_epoch
+            .is_some_and(|underwritten_epoch| Runtime::current_epoch().number() >= underwritten_epoch + insurance.coverage_epochs);
+        let settled = matches!(self.status(), EscrowStatus::Cancelled | EscrowStatus::Withdrawn | EscrowStatus::Expired);
+        assert!(
+            self.status != EscrowStatus::Disputed && (coverage_ended || settled),
+            "Coverage can still be triggered"
+        );
+
+        self.coverage.as_mut().unwrap().take_all()
+    }
+
+    pub fn details(&self, escrow_nft: ResourceAddress) -> EscrowDetails {
+        EscrowDetails {
+            requested_resources: self.requested_resources.clone(),
+            offered_resources: amounts_in(&self.offered_resources),
+            collected_resources: amounts_in(&self.requested_resource_vaults),
+            filled_amounts: self.filled_amounts.clone(),
+            escrow_nft,
+            config: self.config.clone(),
+            status: self.status(),
+            chosen_alternative: self.chosen_alternative.clone(),
+            paused: self.paused,
+        }
+    }
+
+    // Reports open escrows past their deadline as expired
+    pub fn status(&self) -> EscrowStatus {
+        if self.status == EscrowStatus::Open && self.is_expired() {
+            EscrowStatus::Expired
+        } else {
+            self.status
+        }
+    }
+
+    pub fn remaining_amount(&self, resource_address: ResourceAddress) -> Decimal {
+        if let Some(price) = self.config.currency_prices.get(&resource_address) {
+            return *price * self.unfilled_fraction();
+        }
+
+        let requested_resource = self.requested_resources
+            .iter()
+            .find(|requested_resource| requested_resource.get_resource_address() == resource_address)
+            .expect("Resource is not requested by this escrow");
+
+        match requested_resource {
+            ResourceSpecifier::Fungible { amount, .. } if !self.config.currency_prices.is_empty() => {
+                *amount * self.unfilled_fraction()
+            },
+            ResourceSpecifier::Fungible { amount, .. } => {
+                self.current_price().unwrap_or(*amount) - self.filled_amounts[&resource_address]
+            },
+            ResourceSpecifier::NonFungible { .. } | ResourceSpecifier::NonFungibleCount { .. } => {
+                panic!("Only fungible escrows track filled amounts")
+            },
+        }
+    }
+
+    // Payment a taker has to make to receive the given amount of an offered resource, rounded the way a fill rounds.
+    // Fees come out of the instantiator's proceeds, so they are reported next to the payment rather than added to it.
+    pub fn required_payment(&self, resource_address: ResourceAddress, amount: Decimal, fee_bps: u16) -> RequiredPayment {
+        let offered_amount = self.offered_resources
+            .get(&resource_address)
+            .expect("Resource is not offered by this escrow")
+            .amount();
+        assert!(amount > Decimal::ZERO && amount <= offered_amount, "Amount must be positive and at most the offered amount");
+
+        let payment: Vec<ResourceSpecifier> = if self.config.allow_partial_fills {
+            let requested_address = self.requested_resources[0].get_resource_address();
+            let remaining_amount = self.remaining_amount(requested_address);
+
+            let payment_amount = match self.curve_quote() {
+                Some((bonding_curve, start_price, sold_amount)) => bonding_curve
+                    .cost(start_price, sold_amount, amount)
+                    .checked_round(divisibility_of(requested_address), RoundingMode::ToPositiveInfinity)
+                    .unwrap()
+                    .min(remaining_amount),
+                None => required_payment_amount(
+                    amount,
+                    offered_amount,
+                    remaining_amount,
+                    divisibility_of(resource_address),
+                    divisibility_of(requested_address),
+                ),
+            };
+
+            vec![ResourceSpecifier::Fungible { resource_address: requested_address, amount: payment_amount }]
+        } else {
+            assert!(amount == offered_amount, "Escrow can only be filled completely");
+            self.requested_resources
+                .iter()
+                .map(|requested_resource| match requested_resource {
+                    ResourceSpecifier::Fungible { resource_address, .. } => ResourceSpecifier::Fungible {
+                        resource_address: *resource_address,
+                        amount: self.remaining_amount(*resource_address),
+                    },
+                    other => other.clone(),
+                })
+                .collect()
+        };
+
+        let fees = payment
+            .iter()
+            .filter_map(|requested_resource| match requested_resource {
+                ResourceSpecifier::Fungible { resource_address, amount } if fee_bps > 0 => {
+                    let fee_amount = fee_amount(*amount, fee_bps)
+                        .checked_round(divisibility_of(*resource_address), RoundingMode::ToZero)
+                        .unwrap();
+                    Some((*resource_address, fee_amount))
+                },
+                _ => None,
+            })
+            .collect();
+
+        RequiredPayment { payment, fees }
+    }
+
+    // Dry run of paying the given amount of a resource, without touching any vault.
+    // Reports the first check the fill would fail, or what the taker would receive and the fees taken.
+    pub fn preview_exchange(&self, resource_address: ResourceAddress, amount: Decimal, fee_bps: u16) -> ExchangePreview {
+        let failed = |reason: &str| ExchangePreview {
+            received: IndexMap::new(),
+            change: amount,
+            fees: IndexMap::new(),
+            failure: Some(reason.to_string()),
+        };
+        if let Some(reason) = self.closed_reason() {
+            return failed(reason);
+        }
+        if self.paused {
+            return failed("Escrow is paused");
+        }
+        if !self.is_price_fresh() {
+            return failed("Oracle price is stale");
+        }
+
+        // Terms the payment would be settled on, the same way a fill picks them
+        let (requested_resource, is_alternative) = match self.requested_resources.as_slice() {
+            [requested_resource] if requested_resource.get_resource_address() == resource_address => {
+                (requested_resource.clone(), false)
+            },
+            _ => match self.config.currency_prices.get(&resource_address) {
+                Some(price) => (ResourceSpecifier::Fungible { resource_address, amount: *price }, false),
+                None => match self.config.alternative_resources
+                    .iter()
+                    .find(|alternative| alternative.get_resource_address() == resource_address)
+                {
+                    Some(alternative) => (alternative.clone(), true),
+                    None => return failed("Resource is not accepted by this escrow"),
+                },
+            },
+        };
+        let remaining_amount = match &requested_resource {
+            ResourceSpecifier::Fungible { amount, .. } if is_alternative => *amount,
+            ResourceSpecifier::Fungible { .. } => self.remaining_amount(resource_address),
+            ResourceSpecifier::NonFungible { non_fungible_local_ids, .. } => Decimal::from(non_fungible_local_ids.len()),
+            ResourceSpecifier::NonFungibleCount { count, .. } => Decimal::from(*count),
+        };
+
+        if remaining_amount <= Decimal::ZERO {
+            return failed("Escrow is already filled");
+        }
+        if !self.config.allow_partial_fills && amount < remaining_amount {
+            return failed("Insufficient amount of resource");
+        }
+        if self.config.matching_mode == MatchingMode::Exact && amount > remaining_amount {
+            return failed("Payment has to match the requested resources exactly");
+        }
+        let fill_amount = amount.min(remaining_amount);
+        if fill_amount <= Decimal::ZERO {
+            return failed("Empty payment bucket");
+        }
+
+        // A partial fill only releases its proportional slice of the offered resources,
+        // or whatever it buys along the bonding curve
+        let curve_quote = self.curve_quote();
+        let received = self.offered_resources
+            .iter()
+            .map(|(offered_address, vault)| {
+                let received_amount = if fill_amount < remaining_amount {
+                    let received_amount = match &curve_quote {
+                        Some((bonding_curve, start_price, sold_amount)) => bonding_curve
+                            .amount_for(*start_price, *sold_amount, fill_amount)
+                            .min(vault.amount()),
+                        None => proportional_amount(vault.amount(), fill_amount, remaining_amount),
+                    };
+                    received_amount.checked_round(divisibility_of(*offered_address), RoundingMode::ToZero).unwrap()
+                } else {
+                    vault.amount()
+                };
+                (*offered_address, received_amount)
+            })
+            .collect();
+
+        let mut fees = IndexMap::new();
+        if fee_bps > 0 && resource_address.is_fungible() {
+            let fee_amount = fee_amount(fill_amount, fee_bps)
+                .checked_round(divisibility_of(resource_address), RoundingMode::ToZero)
+                .unwrap();
+            fees.insert(resource_address, fee_amount);
+        }
+
+        ExchangePreview {
+            received,
+            change: amount - fill_amount,
+            fees,
+            failure: None,
+        }
+    }
+
+    pub fn current_price(&self) -> Option<Decimal> {
+        // Per-unit escrows ask the unit price for everything still offered, on top of what has been sold
+        if self.config.per_unit_pricing {
+            let (resource_address, unit_price) = match self.requested_resources.as_slice() {
+                [ResourceSpecifier::Fungible { resource_address, amount }] => (*resource_address, *amount),
+                _ => unreachable!(),
+            };
+            let offered_amount = self.offered_resources.values().map(|vault| vault.amount()).next().unwrap();
+            // Along a bonding curve, what is still offered costs whatever is left of the curve
+            if let Some((bonding_curve, start_price, sold_amount)) = self.curve_quote() {
+                return Some(self.filled_amounts[&resource_address] + bonding_curve.cost(start_price, sold_amount, offered_amount));
+            }
+            return Some(self.filled_amounts[&resource_address] + offered_amount * unit_price);
+        }
+
+        if let Some(dutch_auction) = &self.config.dutch_auction {
+            return Some(dutch_auction.amount_at(Runtime::current_epoch().number()));
+        }
+
+        // Priced escrows have a single requested leg
+        self.config
+            .oracle_pricing
+            .as_ref()
+            .map(|oracle_pricing| oracle_pricing.amount_of(self.requested_resources[0].get_resource_address()))
+    }
+
+    // Bonding curve of a per-unit escrow, with the unit price it starts at and how much of the offer it has sold
+    fn curve_quote(&self) -> Option<(BondingCurve, Decimal, Decimal)> {
+        let bonding_curve = self.config.bonding_curve.clone()?;
+        let (resource_address, start_price) = match self.requested_resources.as_slice() {
+            [ResourceSpecifier::Fungible { resource_address, amount }] => (*resource_address, *amount),
+            _ => unreachable!(),
+        };
+        let paid_amount = self.filled_amounts.get(&resource_address).copied().unwrap_or(Decimal::ZERO);
+        let sold_amount = bonding_curve.amount_for(start_price, Decimal::ZERO, paid_amount);
+
+        Some((bonding_curve, start_price, sold_amount))
+    }
+
+    // Index of the tranche the next offered unit of a tranche-priced escrow sells in
+    pub fn current_tranche(&self) -> u32 {
+        let (bonding_curve, _, sold_amount) = self.curve_quote().expect("Escrow is not priced in tranches");
+        bonding_curve.tranche_index(sold_amount).expect("Escrow is not priced in tranches")
+    }
+
+    // Closes a sale-capped escrow to fills once it has collected its cap
+    fn mark_if_sold_out(&mut self) {
+        let Some(sale_cap) = self.config.sale_cap else {
+            return;
+        };
+        let collected = self.filled_amounts
+            .get(&self.requested_resources[0].get_resource_address())
+            .copied()
+            .unwrap_or_default();
+        if collected < sale_cap {
+            return;
+        }
+        self.status = EscrowStatus::SoldOut;
+
+        Runtime::emit_event(EscrowSoldOut {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            collected,
+            unsold: amounts_in(&self.offered_resources),
+        });
+    }
+
+    // Records the oracle price for the escrow's own time-weighted average, once per epoch,
+    // dropping whatever no longer stands within the averaging window
+    pub fn observe_price(&mut self) {
+        let oracle_pricing = self.config.oracle_pricing.clone().expect("Escrow is not oracle-priced");
+        let Some(TwapGuard { source: TwapSource::Observations { window_epochs }, .. }) = oracle_pricing.twap_guard else {
+            panic!("Escrow does not observe prices");
+        };
+        let now = Runtime::current_epoch().number();
+        if self.price_observations.last().is_some_and(|(epoch, _)| *epoch == now) {
+            return;
+        }
+        let price = oracle_pricing.price(self.requested_resources[0].get_resource_address());
+        self.price_observations.push((now, price));
+
+        // The newest observation from before the window still stands at its start
+        let start_epoch = now.saturating_sub(window_epochs);
+        let stale = self.price_observations.iter().take_while(|(epoch, _)| *epoch <= start_epoch).count();
+        if stale > 1 {
+            self.price_observations.drain(..stale - 1);
+        }
+    }
+
+    // Only lets a fill through at an oracle price within the guard's band around the time-weighted average,
+    // recording the price afterwards when the escrow keeps its own observations
+    fn assert_near_twap(&mut self) {
+        let Some(oracle_pricing) = self.config.oracle_pricing.clone() else {
+            return;
+        };
+        let Some(twap_guard) = &oracle_pricing.twap_guard else {
+            return;
+        };
+        let resource_address = self.requested_resources[0].get_resource_address();
+        let twap = match twap_guard.source {
+            TwapSource::Oracle => oracle_pricing.oracle_twap(resource_address),
+            TwapSource::Observations { window_epochs } => {
+                let now = Runtime::current_epoch().number();
+                time_weighted_average(&self.price_observations, now.saturating_sub(window_epochs), now)
+                    .expect("No price observations to compare the oracle price with")
+            },
+        };
+        let deviation = (oracle_pricing.price(resource_address) - twap).checked_abs().unwrap();
+        assert!(
+            deviation <= fee_amount(twap, twap_guard.max_deviation_bps),
+            "Oracle price is too far from its time-weighted average"
+        );
+
+        if twap_guard.source != TwapSource::Oracle {
+            self.observe_price();
+        }
+    }
+
+    // Amount a taker has paid into a capped escrow so far
+    pub fn taker_filled_amount(&self, taker_id: &TakerId) -> Decimal {
+        self.taker_fills.get(taker_id).map_or(Decimal::ZERO, |taker_filled| *taker_filled)
+    }
+
+    // Price of the next offered unit of a per-unit escrow, which climbs along its bonding curve if it has one
+    pub fn unit_price(&self) -> Decimal {
+        assert!(self.config.per_unit_pricing, "Escrow is not priced per unit");
+        match self.curve_quote() {
+            Some((bonding_curve, start_price, sold_amount)) => bonding_curve.price_at(start_price, sold_amount),
+            None => match self.requested_resources.as_slice() {
+                [ResourceSpecifier::Fungible { amount, .. }] => *amount,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    pub fn is_price_fresh(&self) -> bool {
+        match &self.config.oracle_pricing {
+            Some(oracle_pricing) => oracle_pricing.is_price_fresh(self.requested_resources[0].get_resource_address()),
+            None => true,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        let epoch_passed = self.config.expiry_epoch.is_some_and(|expiry_epoch| Runtime::current_epoch().number() >= expiry_epoch);
+        epoch_passed || self.config.expiry_time.is_some_and(|expiry_time| expiry_time.has_passed())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.status() != EscrowStatus::Open
+    }
+
+    // Records alternative terms proposed by a prospective taker, returning the ID of the counter-offer
+    pub fn propose_counter_offer(
+        &mut self,
+        requested_resources: Vec<ResourceSpecifier>,
+        proposer: Option<ComponentAddress>
+    ) -> u64 {
+        self.assert_open();
+        assert!(!requested_resources.is_empty(), "At least one requested resource is required");
+
+        let counter_offer_id = self.counter_offer_count;
+        self.counter_offers.insert(counter_offer_id, CounterOffer {
+            requested_resources: requested_resources.clone(),
+            proposed_epoch: Runtime::current_epoch().number(),
+            status: CounterOfferStatus::Pending,
+            proposer,
+        });
+        self.counter_offer_count += 1;
+
+        Runtime::emit_event(CounterOfferProposed {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            counter_offer_id,
+            requested_resources,
+        });
+
+        counter_offer_id
+    }
+
+    // Replaces the requested terms with the ones of a pending counter-offer
+    pub fn accept_counter_offer(&mut self, counter_offer_id: u64) {
+        self.assert_open();
+        assert!(self.is_unfilled(), "Terms can only change before the escrow is filled");
+
+        let (requested_resources, proposer) = {
+            let mut counter_offer = self.counter_offers.get_mut(&counter_offer_id).expect("Counter-offer not found");
+            assert!(counter_offer.status == CounterOfferStatus::Pending, "Counter-offer is no longer pending");
+            counter_offer.status = CounterOfferStatus::Accepted;
+            (counter_offer.requested_resources.clone(), counter_offer.proposer)
+        };
+        self.set_requested_resources(requested_resources);
+        self.engaged_counterparty = proposer;
+
+        Runtime::emit_event(CounterOfferAccepted {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            counter_offer_id,
+        });
+    }
+
+    // Replaces the requested terms directly, on behalf of the instantiator
+    pub fn update_requested(&mut self, requested_resources: Vec<ResourceSpecifier>) {
+        self.assert_open();
+        assert!(self.is_unfilled(), "Terms can only change before the escrow is filled");
+        self.set_requested_resources(requested_resources.clone());
+
+        Runtime::emit_event(RequestedUpdated {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            requested_resources,
+        });
+    }
+
+    pub fn reject_counter_offer(&mut self, counter_offer_id: u64) {
+        let mut counter_offer = self.counter_offers.get_mut(&counter_offer_id).expect("Counter-offer not found");
+        assert!(counter_offer.status == CounterOfferStatus::Pending, "Counter-offer is no longer pending");
+        counter_offer.status = CounterOfferStatus::Rejected;
+
+        Runtime::emit_event(CounterOfferRejected {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            counter_offer_id,
+        });
+    }
+
+    pub fn counter_offer(&self, counter_offer_id: u64) -> Option<CounterOffer> {
+        self.counter_offers.get(&counter_offer_id).map(|counter_offer| counter_offer.clone())
+    }
+
+    // Checks that nothing has been paid into the escrow yet
+    pub fn is_unfilled(&self) -> bool {
+        self.filled_amounts.values().all(|filled_amount| filled_amount.is_zero())
+            && self.requested_resource_vaults.values().all(|vault| vault.is_empty())
+            && self.taker_deposit.values().all(|vault| vault.is_empty())
+    }
+
+    // Validates and stores new requested terms, with a vault for every requested resource.
+    // Vaults of resources that are no longer requested are kept, as vaults cannot be dropped.
+    fn set_requested_resources(&mut self, requested_resources: Vec<ResourceSpecifier>) {
+        assert!(!requested_resources.is_empty(), "At least one requested resource is required");
+
+        // Partial fills release a proportional slice of the offered resources against a single fungible leg,
+        // and Dutch auctions and oracles price that single leg
+        if self.config.allow_partial_fills
+            || self.config.dutch_auction.is_some()
+            || self.config.oracle_pricing.is_some()
+            || !self.config.currency_prices.is_empty()
+        {
+            assert!(
+                matches!(requested_resources.as_slice(), [ResourceSpecifier::Fungible { .. }]),
+                "Partial fills and priced escrows require a single fungible requested resource"
+            );
+            assert!(
+                !self.config.currency_prices.contains_key(&requested_resources[0].get_resource_address()),
+                "Requested resource is already an accepted currency"
+            );
+        }
+
+        let mut filled_amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::new();
+        for requested_resource in &requested_resources {
+            let resource_address = requested_resource.get_resource_address();
+            assert!(
+                requested_resources.iter().filter(|other| other.get_resource_address() == resource_address).count() == 1,
+                "Duplicate requested resource"
+            );
+            self.requested_resource_vaults
+                .entry(resource_address)
+                .or_insert_with(|| Vault::new(resource_address));
+
+            match requested_resource {
+                ResourceSpecifier::Fungible { .. } => {
+                    filled_amounts.insert(resource_address, Decimal::ZERO);
+                },
+                ResourceSpecifier::NonFungible { non_fungible_local_ids, .. } => {
+                    assert!(!non_fungible_local_ids.is_empty(), "At least one non-fungible has to be requested");
+                },
+                ResourceSpecifier::NonFungibleCount { count, .. } => {
+                    assert!(!resource_address.is_fungible(), "Counted requests require a non-fungible resource");
+                    assert!(*count > 0, "At least one non-fungible has to be requested");
+                },
+            }
+        }
+
+        self.requested_resources = requested_resources;
+        self.filled_amounts = filled_amounts;
+    }
+
+    // Splits the collected payment between the instantiator and the taker.
+    // The taker's share is set aside for them to claim, the rest is released to the instantiator again.
+    fn apply_verdict(&mut self, award: DisputeAward) {
+        let taker_share = award.taker_share();
+
+        // Rounding towards zero keeps any remainder with the instantiator, and whole non-fungibles only
+        for (resource_address, vault) in self.requested_resource_vaults.iter_mut() {
+            let refund = vault.take_advanced(vault.amount() * taker_share, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+            self.refund_vaults
+                .entry(*resource_address)
+                .or_insert_with(|| Vault::new(*resource_address))
+                .put(refund);
+        }
+        self.status = if taker_share == Decimal::ONE { EscrowStatus::Refunded } else { EscrowStatus::Filled };
+
+        Runtime::emit_event(DisputeResolved {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            award,
+        });
+
+        // A split verdict has no losing side to slash or pay the coverage of
+        let loser = match award {
+            DisputeAward::Maker => Some(SettlementParty::Taker),
+            DisputeAward::Taker => Some(SettlementParty::Maker),
+            DisputeAward::Split { .. } => None,
+        };
+        if let Some(loser) = loser {
+            let winner = if loser == SettlementParty::Maker { SettlementParty::Taker } else { SettlementParty::Maker };
+            self.slash_bond(loser, winner);
+            self.trigger_coverage(loser);
+        }
+    }
+
+    // Pays the coverage out to an insured party that lost a dispute within the coverage window
+    fn trigger_coverage(&mut self, loser: SettlementParty) {
+        let (Some(insurance), Some(underwritten_epoch)) = (&self.config.insurance, self.underwritten_epoch) else {
+            return;
+        };
+        if insurance.insured != loser
+            || Runtime::current_epoch().number() >= underwritten_epoch + insurance.coverage_epochs
+        {
+            return;
+        }
+        self.coverage_triggered = true;
+
+        Runtime::emit_event(CoveragePaidOut {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            party: loser,
+            amount: self.coverage.as_ref().unwrap().amount(),
+        });
+    }
+
+    fn insurance_of(&self, party: SettlementParty) -> InsuranceTerms {
+        let insurance = self.config.insurance.clone().expect("Escrow is not insurable");
+        assert!(insurance.insured == party, "Only the insured party can do this");
+        insurance
+    }
+
+    // Moves the slashed part of the loser's bond into the winner's, less the cut of the arbiter
+    fn slash_bond(&mut self, loser: SettlementParty, winner: SettlementParty) {
+        let Some(collateral_bond) = self.config.collateral_bond.clone() else {
+            return;
+        };
+        let loser_bond = self.bond_vault(loser);
+        let mut slashed = loser_bond.take_advanced(
+            fee_amount(loser_bond.amount(), collateral_bond.slash_bps),
+            WithdrawStrategy::Rounded(RoundingMode::ToZero),
+        );
+        let slashed_amount = slashed.amount();
+        let arbiter_cut = slashed.take_advanced(
+            fee_amount(slashed_amount, collateral_bond.arbiter_bps),
+            WithdrawStrategy::Rounded(RoundingMode::ToZero),
+        );
+        let arbiter_cut_amount = arbiter_cut.amount();
+        self.arbiter_bond_cut.as_mut().unwrap().put(arbiter_cut);
+        self.bond_vault(winner).put(slashed);
+
+        Runtime::emit_event(BondSlashed {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            party: loser,
+            slashed: slashed_amount,
+            arbiter_cut: arbiter_cut_amount,
+        });
+    }
+
+    fn bond_vault(&mut self, party: SettlementParty) -> &mut Vault {
+        match party {
+            SettlementParty::Maker => self.maker_bond.as_mut(),
+            SettlementParty::Taker => self.taker_bond.as_mut(),
+        }
+        .expect("Escrow takes no collateral bonds")
+    }
+
+    // Bonds back the escrow for as long as a dispute can still be raised or awaits its verdict
+    fn bonds_locked(&self) -> bool {
+        match self.status {
+            EscrowStatus::Disputed => true,
+            EscrowStatus::Filled => {
+                !self.disputed && (self.config.dispute_window_epochs.is_none() || self.in_dispute_window())
+            },
+            _ => false,
+        }
+    }
+
+    // Checks whether a filled escrow is still within its dispute window, which a resolved dispute closes early
+    fn in_dispute_window(&self) -> bool {
+        match (self.config.dispute_window_epochs, self.first_fill_epoch) {
+            (Some(dispute_window_epochs), Some(filled_epoch)) => {
+                self.status == EscrowStatus::Filled
+                    && !self.disputed
+                    && Runtime::current_epoch().number() < filled_epoch + dispute_window_epochs
+            },
+            _ => false,
+        }
+    }
+
+    // Share of the collected resources the instantiator is allowed to have withdrawn by now.
+    // Vesting proceeds unlock linearly from the first fill, everything else is unlocked right away.
+    fn vested_fraction(&self) -> Decimal {
+        match (self.config.vesting_epochs, self.first_fill_epoch) {
+            (Some(vesting_epochs), Some(first_fill_epoch)) => {
+                let elapsed_epochs = Runtime::current_epoch().number().saturating_sub(first_fill_epoch);
+                vested_fraction(elapsed_epochs, vesting_epochs)
+            },
+            _ => Decimal::ONE,
+        }
+    }
+
+    fn verify_recovery_authority(&self, authority: Proof) -> BadgeRecovery {
+        let badge_recovery = self.config.badge_recovery.clone().expect("Escrow has no recovery authority");
+        authority.check_with_message(badge_recovery.authority, "Invalid recovery authority");
+        badge_recovery
+    }
+
+    // Private escrows can only be filled by takers presenting the access badge in their auth zone.
+    // Returns the local ID of the taker's compliance credential, if the escrow asks for one.
+    fn assert_taker_allowed(&self, taker: TakerInput) -> Option<NonFungibleLocalId> {
+        assert!(!self.paused, "Escrow is paused");
+        let TakerInput { secret, terms_hash, credential, account, .. } = taker;
+
+        if let Some(access_badge) = self.config.access_badge {
+            Runtime::assert_access_rule(rule!(require(access_badge)));
+        }
+
+        // A taker naming their account has to be its owner
+        if let Some(account) = account {
+            let account: Global<Account> = account.into();
+            Runtime::assert_access_rule(account.get_owner_role().rule);
+        }
+
+        // Allowlisted escrows settle with a listed account, or a listed component calling in
+        if let Some(taker_allowlist) = &self.config.taker_allowlist {
+            match account {
+                Some(account) => assert!(taker_allowlist.contains(&account), "Taker is not on the allowlist"),
+                None => {
+                    let allowed_callers: Vec<ResourceOrNonFungible> = taker_allowlist
+                        .iter()
+                        .map(|taker| global_caller(*taker))
+                        .collect();
+                    Runtime::assert_access_rule(rule!(require_any_of(allowed_callers)));
+                },
+            }
+        }
+
+        // Regulated escrows only settle with holders of the credential, whose ID goes on record with the fill
+        let credential_id = self.config.compliance_credential.map(|compliance_credential| {
+            credential
+                .expect("Escrow requires a compliance credential")
+                .check_with_message(compliance_credential, "Invalid compliance credential")
+                .non_fungible_local_id()
+        });
+
+        // Invite-only escrows can only be filled with the code shared by the instantiator
+        if let Some(invite_code_hash) = self.config.invite_code_hash {
+            let invite_code = secret.as_ref().expect("Escrow requires an invite code");
+            assert!(hash(invite_code) == invite_code_hash, "Invalid invite code");
+        }
+
+        // Terms-bound escrows only settle with takers who accepted the very same terms
+        if let Some(terms) = &self.config.terms {
+            match (terms_hash, terms.acceptance_badge) {
+                (Some(terms_hash), _) => assert!(terms_hash == terms.hash, "Terms do not match the escrow"),
+                (None, Some(acceptance_badge)) => Runtime::assert_access_rule(rule!(require(acceptance_badge))),
+                (None, None) => panic!("Escrow requires accepting its terms"),
+            }
+        }
+
+        // Hash-locked escrows can only be filled by revealing the secret, which the event makes visible to watchers
+        if let Some(hash_lock) = &self.config.hash_lock {
+            let secret = secret.expect("Escrow is locked with a secret");
+            assert!(CryptoUtils::keccak256_hash(secret.clone()) == hash_lock.hash, "Invalid secret");
+            Runtime::emit_event(SecretRevealed {
+                escrow_nft_id: self.escrow_nft_id.clone(),
+                secret,
+            });
+        }
+
+        credential_id
+    }
+
+    fn terms_hash(&self) -> Option<Hash> {
+        self.config.terms.as_ref().map(|terms| terms.hash)
+    }
+
+    // Aborts any fill against an escrow that is no longer open
+    fn assert_open(&self) {
+        if let Some(reason) = self.closed_reason() {
+            panic!("{}", reason);
+        }
+    }
+
+    // Reason an escrow cannot be filled, if there is one
+    fn closed_reason(&self) -> Option<&'static str> {
+        match self.status() {
+            EscrowStatus::Open => {},
+            EscrowStatus::Filled | EscrowStatus::Withdrawn => return Some("Escrow has already been filled"),
+            EscrowStatus::Cancelled => return Some("Escrow has been cancelled"),
+            EscrowStatus::SoldOut => return Some("Escrow is sold out"),
+            EscrowStatus::Closed => return Some("Escrow has been closed"),
+            EscrowStatus::Disputed => return Some("Escrow is in dispute"),
+            EscrowStatus::Refunded => return Some("Escrow has been refunded"),
+            // Expired escrows only allow the instantiator to reclaim the offered resource
+            EscrowStatus::Expired => return Some("Escrow has expired"),
+        }
+        if self.offered_resources.values().all(|vault| vault.is_empty()) {
+            return Some("Escrow has nothing left to offer");
+        }
+        None
+    }
+
+    fn mint_taker_badge(&mut self) -> Bucket {
+        let taker_nft = ResourceManager::from(self.taker_badge).mint_ruid_non_fungible(TakerBadge {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            escrow_component: Runtime::global_address(),
+            filled_epoch: Runtime::current_epoch().number(),
+        });
+        self.taker_nft_id = Some(taker_nft.as_non_fungible().non_fungible_local_id());
+        taker_nft
+    }
+
+    fn mint_taker_receipt(
+        &self,
+        paid: IndexMap<ResourceAddress, Decimal>,
+        received: IndexMap<ResourceAddress, Decimal>
+    ) -> Bucket {
+        let price = match (paid.len(), received.values().next()) {
+            (1, Some(received_amount)) if received.len() == 1 && received_amount.is_positive() => {
+                Some(paid[0] / *received_amount)
+            },
+            _ => None,
+        };
+
+        ResourceManager::from(self.taker_receipt.unwrap()).mint_ruid_non_fungible(TakerReceipt {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            escrow_component: Runtime::global_address(),
+            paid,
+            received,
+            price,
+            epoch: Runtime::current_epoch().number(),
+        })
+    }
+
+    fn mint_reputation_attestation(&self, party: SettlementParty) -> Bucket {
+        ResourceManager::from(self.reputation_attestation.unwrap()).mint_ruid_non_fungible(ReputationAttestation {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            escrow_component: Runtime::global_address(),
+            party,
+            outcome: self.status,
+            requested_resources: self.requested_resources.clone(),
+            epoch: Runtime::current_epoch().number(),
+        })
+    }
+
+    // Keeps what a party is owed in vaults of its own, minting the claim to redeem it with
+    fn hold_for_claim(&mut self, owed: Vec<Bucket>, party: SettlementParty) -> Bucket {
+        let claim_nft = ResourceManager::from(self.settlement_claim.unwrap()).mint_ruid_non_fungible(SettlementClaim {
+            escrow_nft_id: self.escrow_nft_id.clone(),
+            escrow_component: Runtime::global_address(),
+            party,
+            amounts: amounts_of(&owed),
+            created_epoch: Runtime::current_epoch().number(),
+        });
+        self.claim_vaults.insert(claim_nft.as_non_fungible().non_fungible_local_id(), vaults_of(owed));
+
+        claim_nft
+    }
+
+    // Splits the cancellation penalty off the fungible buckets of a side backing out of a committed deal
+    fn take_penalty(&self, buckets: &mut [Bucket]) -> Vec<Bucket> {
+        if self.config.cancellation_penalty_bps == 0 {
+            return Vec::new();
+        }
+        buckets
+            .iter_mut()
+            .filter(|bucket| bucket.resource_address().is_fungible())
+            .map(|bucket| {
+                let penalty_amount = fee_amount(bucket.amount(), self.config.cancellation_penalty_bps);
+                bucket.take_advanced(penalty_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+            })
+            .collect()
+    }
+
+    fn take_taker_deposit(&mut self) -> Vec<Bucket> {
+        self.taker_deposit.values_mut().map(|vault| vault.take_all()).collect()
+    }
+
+    // Empties every vault of the offered bundle
+    fn take_all_offered(&mut self) -> Vec<Bucket> {
+        self.offered_resources.values_mut().map(|vault| vault.take_all()).collect()
+    }
+}
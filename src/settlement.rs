@@ -0,0 +1,319 @@
+use scrypto::prelude::*;
+
+// Fee rates are expressed in basis points, so this is a fee of 100%
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+// Slice of a balance matching the share `part / whole`, e.g. the offered resources released by a partial fill
+pub fn proportional_amount(balance: Decimal, part: Decimal, whole: Decimal) -> Decimal {
+    balance * part / whole
+}
+
+// Protocol fee on an amount, at a rate in basis points
+pub fn fee_amount(amount: Decimal, fee_bps: u16) -> Decimal {
+    amount * Decimal::from(fee_bps) / Decimal::from(MAX_FEE_BPS)
+}
+
+// Share of vesting proceeds unlocked after the given number of epochs, growing linearly up to everything
+pub fn vested_fraction(elapsed_epochs: u64, vesting_epochs: u64) -> Decimal {
+    (Decimal::from(elapsed_epochs) / Decimal::from(vesting_epochs)).min(Decimal::ONE)
+}
+
+// Amount that can be withdrawn from a balance now, given what has been withdrawn from it before
+// and the share of everything ever collected that is unlocked
+pub fn unlocked_amount(balance: Decimal, withdrawn_amount: Decimal, vested_fraction: Decimal) -> Decimal {
+    ((balance + withdrawn_amount) * vested_fraction - withdrawn_amount).max(Decimal::ZERO)
+}
+
+// Share of an offer that is still unfilled, given the price of the whole offer and the amount filled
+// in every accepted currency
+pub fn unfilled_fraction(fills: impl IntoIterator<Item = (Decimal, Decimal)>) -> Decimal {
+    fills
+        .into_iter()
+        .fold(Decimal::ONE, |unfilled_fraction, (price, filled_amount)| unfilled_fraction - filled_amount / price)
+}
+
+// Price of a Dutch auction at an epoch, decaying from the start amount to the floor in whole steps
+pub fn dutch_auction_amount(
+    start_amount: Decimal,
+    floor_amount: Decimal,
+    start_epoch: u64,
+    end_epoch: u64,
+    step_epochs: u64,
+    epoch: u64
+) -> Decimal {
+    if epoch <= start_epoch {
+        return start_amount;
+    }
+    if epoch >= end_epoch {
+        return floor_amount;
+    }
+
+    // Only whole steps count, so a stepwise auction holds its price between drops
+    let elapsed_epochs = (epoch - start_epoch) / step_epochs * step_epochs;
+    let decay = (start_amount - floor_amount) * Decimal::from(elapsed_epochs) / Decimal::from(end_epoch - start_epoch);
+
+    start_amount - decay
+}
+
+// Smallest payment, at the divisibility of the requested resource, whose release of the offered resource
+// rounded towards zero covers the desired amount. The payment never exceeds what is left to fill.
+pub fn required_payment_amount(
+    desired_amount: Decimal,
+    offered_amount: Decimal,
+    remaining_amount: Decimal,
+    offered_divisibility: u8,
+    requested_divisibility: u8
+) -> Decimal {
+    let step = Decimal::ONE / Decimal::from(10u64.pow(requested_divisibility.into()));
+    let release_of = |payment_amount: Decimal| proportional_amount(offered_amount, payment_amount, remaining_amount)
+        .checked_round(offered_divisibility, RoundingMode::ToZero)
+        .unwrap();
+
+    // Rounding the payment up, and then further until the released slice covers the amount
+    let mut payment_amount = proportional_amount(remaining_amount, desired_amount, offered_amount)
+        .checked_round(requested_divisibility, RoundingMode::ToPositiveInfinity)
+        .unwrap()
+        .min(remaining_amount);
+    while release_of(payment_amount) < desired_amount && payment_amount < remaining_amount {
+        payment_amount = (payment_amount + step).min(remaining_amount);
+    }
+    payment_amount
+}
+
+// Cost of buying `amount` units along a linear curve, once `sold` units have been sold.
+// The unit price starts at `start_price` and grows by `slope` with every unit sold.
+pub fn linear_curve_cost(start_price: Decimal, slope: Decimal, sold: Decimal, amount: Decimal) -> Decimal {
+    amount * (start_price + slope * sold) + slope * amount * amount / Decimal::from(2)
+}
+
+// Units a payment buys along a linear curve, once `sold` units have been sold
+pub fn linear_curve_amount(start_price: Decimal, slope: Decimal, sold: Decimal, payment: Decimal) -> Decimal {
+    let price = start_price + slope * sold;
+    if slope == Decimal::ZERO {
+        return payment / price;
+    }
+    // Solving `slope / 2 * amount² + price * amount = payment` for the amount
+    ((price * price + Decimal::from(2) * slope * payment).checked_sqrt().unwrap() - price) / slope
+}
+
+// Unit price of a stepped exponential curve at a position, multiplied by `growth_factor` every `step_amount` units
+pub fn stepped_curve_price(start_price: Decimal, growth_factor: Decimal, step_amount: Decimal, position: Decimal) -> Decimal {
+    let step = i64::try_from((position / step_amount).checked_floor().unwrap()).unwrap();
+    start_price * growth_factor.checked_powi(step).unwrap()
+}
+
+// Cost of buying `amount` units along a stepped exponential curve, once `sold` units have been sold
+pub fn stepped_curve_cost(
+    start_price: Decimal,
+    growth_factor: Decimal,
+    step_amount: Decimal,
+    sold: Decimal,
+    amount: Decimal
+) -> Decimal {
+    let end = sold + amount;
+    let mut position = sold;
+    let mut cost = Decimal::ZERO;
+    while position < end {
+        let price = stepped_curve_price(start_price, growth_factor, step_amount, position);
+        let step_end = (((position / step_amount).checked_floor().unwrap() + Decimal::ONE) * step_amount).min(end);
+        cost += (step_end - position) * price;
+        position = step_end;
+    }
+    cost
+}
+
+// Units a payment buys along a stepped exponential curve, once `sold` units have been sold
+pub fn stepped_curve_amount(
+    start_price: Decimal,
+    growth_factor: Decimal,
+    step_amount: Decimal,
+    sold: Decimal,
+    payment: Decimal
+) -> Decimal {
+    let mut position = sold;
+    let mut left = payment;
+    while left > Decimal::ZERO {
+        let price = stepped_curve_price(start_price, growth_factor, step_amount, position);
+        let step_left = ((position / step_amount).checked_floor().unwrap() + Decimal::ONE) * step_amount - position;
+        if left <= step_left * price {
+            return position + left / price - sold;
+        }
+        left -= step_left * price;
+        position += step_left;
+    }
+    position - sold
+}
+
+// Tranche of an offer sold in tranches of (amount, unit price) at a position, with its unit price and end.
+// Everything beyond the last tranche sells at its price.
+pub fn tranche_at(tranches: &[(Decimal, Decimal)], position: Decimal) -> (usize, Decimal, Option<Decimal>) {
+    let mut end = Decimal::ZERO;
+    for (index, (amount, price)) in tranches.iter().enumerate() {
+        end += *amount;
+        if position < end {
+            return (index, *price, Some(end));
+        }
+    }
+    (tranches.len() - 1, tranches[tranches.len() - 1].1, None)
+}
+
+// Cost of buying `amount` units of an offer sold in tranches, once `sold` units have been sold
+pub fn tranche_cost(tranches: &[(Decimal, Decimal)], sold: Decimal, amount: Decimal) -> Decimal {
+    let end = sold + amount;
+    let mut position = sold;
+    let mut cost = Decimal::ZERO;
+    while position < end {
+        let (_, price, tranche_end) = tranche_at(tranches, position);
+        let step_end = tranche_end.map_or(end, |tranche_end| tranche_end.min(end));
+        cost += (step_end - position) * price;
+        position = step_end;
+    }
+    cost
+}
+
+// Units a payment buys of an offer sold in tranches, once `sold` units have been sold
+pub fn tranche_amount(tranches: &[(Decimal, Decimal)], sold: Decimal, payment: Decimal) -> Decimal {
+    let mut position = sold;
+    let mut left = payment;
+    while left > Decimal::ZERO {
+        let (_, price, tranche_end) = tranche_at(tranches, position);
+        match tranche_end {
+            Some(tranche_end) if left > (tranche_end - position) * price => {
+                left -= (tranche_end - position) * price;
+                position = tranche_end;
+            },
+            _ => return position + left / price - sold,
+        }
+    }
+    position - sold
+}
+
+// Time-weighted average of price observations since the start epoch, each price standing until the next
+// observation or until now. The latest price is the average when no epoch has passed since the start.
+pub fn time_weighted_average(observations: &[(u64, Decimal)], start_epoch: u64, now: u64) -> Option<Decimal> {
+    let (_, latest_price) = *observations.last()?;
+    let mut weighted_sum = Decimal::ZERO;
+    let mut total_epochs = 0u64;
+    for (index, (epoch, price)) in observations.iter().enumerate() {
+        let from = (*epoch).max(start_epoch);
+        let until = observations.get(index + 1).map_or(now, |(next_epoch, _)| *next_epoch);
+        if until > from {
+            weighted_sum += *price * Decimal::from(until - from);
+            total_epochs += until - from;
+        }
+    }
+    if total_epochs == 0 {
+        return Some(latest_price);
+    }
+    Some(weighted_sum / Decimal::from(total_epochs))
+}
+
+// Position of the entry a seed draws out of `entries` ones, from the first eight bytes of the seed
+pub fn draw_index(seed: Hash, entries: u64) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&seed.as_slice()[..8]);
+    u64::from_le_bytes(bytes) % entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proportional_amount() {
+        assert_eq!(proportional_amount(dec!("100"), dec!("25"), dec!("50")), dec!("50"));
+        assert_eq!(proportional_amount(dec!("100"), dec!("50"), dec!("50")), dec!("100"));
+    }
+
+    #[test]
+    fn test_fee_amount() {
+        assert_eq!(fee_amount(dec!("50"), 100), dec!("0.5"));
+        assert_eq!(fee_amount(dec!("50"), 0), Decimal::ZERO);
+        assert_eq!(fee_amount(dec!("50"), MAX_FEE_BPS), dec!("50"));
+    }
+
+    #[test]
+    fn test_vesting() {
+        assert_eq!(vested_fraction(0, 10), Decimal::ZERO);
+        assert_eq!(vested_fraction(5, 10), dec!("0.5"));
+        assert_eq!(vested_fraction(20, 10), Decimal::ONE);
+
+        // Half of 100 is unlocked, 20 of which have been withdrawn already
+        assert_eq!(unlocked_amount(dec!("80"), dec!("20"), dec!("0.5")), dec!("30"));
+        assert_eq!(unlocked_amount(dec!("40"), dec!("60"), dec!("0.5")), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_unfilled_fraction() {
+        assert_eq!(unfilled_fraction([(dec!("100"), Decimal::ZERO)]), Decimal::ONE);
+        assert_eq!(unfilled_fraction([(dec!("100"), dec!("25")), (dec!("50"), dec!("25"))]), dec!("0.25"));
+    }
+
+    #[test]
+    fn test_dutch_auction_amount() {
+        let amount_at = |epoch| dutch_auction_amount(dec!("100"), dec!("50"), 10, 20, 5, epoch);
+        assert_eq!(amount_at(5), dec!("100"));
+        assert_eq!(amount_at(14), dec!("100"));
+        assert_eq!(amount_at(15), dec!("75"));
+        assert_eq!(amount_at(25), dec!("50"));
+    }
+
+    #[test]
+    fn test_required_payment_amount() {
+        // 100 offered for 50, so 10 of the offered resource cost 5
+        assert_eq!(required_payment_amount(dec!("10"), dec!("100"), dec!("50"), 18, 18), dec!("5"));
+        // 1 of 3 offered for 1 rounds the payment up to the next whole unit
+        assert_eq!(required_payment_amount(dec!("1"), dec!("3"), dec!("1"), 0, 0), dec!("1"));
+        assert_eq!(required_payment_amount(dec!("1"), dec!("3"), dec!("10"), 0, 0), dec!("4"));
+    }
+
+    #[test]
+    fn test_linear_curve() {
+        // Starting at 1 and growing by 1 per unit, the first 2 units cost 1 + 3
+        assert_eq!(linear_curve_cost(dec!("1"), dec!("1"), Decimal::ZERO, dec!("2")), dec!("4"));
+        assert_eq!(linear_curve_amount(dec!("1"), dec!("1"), Decimal::ZERO, dec!("4")), dec!("2"));
+        assert_eq!(linear_curve_cost(dec!("1"), dec!("1"), dec!("2"), dec!("2")), dec!("8"));
+        assert_eq!(linear_curve_amount(dec!("2"), Decimal::ZERO, dec!("5"), dec!("5")), dec!("2.5"));
+    }
+
+    #[test]
+    fn test_stepped_curve() {
+        // Doubling from 1 every 10 units, so 10 at 1 and 5 at 2
+        assert_eq!(stepped_curve_price(dec!("1"), dec!("2"), dec!("10"), dec!("25")), dec!("4"));
+        assert_eq!(stepped_curve_cost(dec!("1"), dec!("2"), dec!("10"), Decimal::ZERO, dec!("15")), dec!("20"));
+        assert_eq!(stepped_curve_amount(dec!("1"), dec!("2"), dec!("10"), Decimal::ZERO, dec!("20")), dec!("15"));
+        assert_eq!(stepped_curve_amount(dec!("1"), dec!("2"), dec!("10"), dec!("5"), dec!("5")), dec!("5"));
+    }
+
+    #[test]
+    fn test_tranches() {
+        // 10 units at 1, then 10 at 2, and 3 beyond that
+        let tranches = [(dec!("10"), dec!("1")), (dec!("10"), dec!("2"))];
+        assert_eq!(tranche_at(&tranches, dec!("5")), (0, dec!("1"), Some(dec!("10"))));
+        assert_eq!(tranche_at(&tranches, dec!("10")), (1, dec!("2"), Some(dec!("20"))));
+        assert_eq!(tranche_at(&tranches, dec!("25")), (1, dec!("2"), None));
+        assert_eq!(tranche_cost(&tranches, dec!("5"), dec!("10")), dec!("15"));
+        assert_eq!(tranche_amount(&tranches, dec!("5"), dec!("15")), dec!("10"));
+        assert_eq!(tranche_amount(&tranches, dec!("15"), dec!("20")), dec!("10"));
+    }
+
+    #[test]
+    fn test_time_weighted_average() {
+        // 10 for 10 epochs, then 20 for another 10
+        let observations = [(0, dec!("10")), (10, dec!("20"))];
+        assert_eq!(time_weighted_average(&observations, 0, 20), Some(dec!("15")));
+        assert_eq!(time_weighted_average(&observations, 10, 20), Some(dec!("20")));
+        assert_eq!(time_weighted_average(&observations, 0, 10), Some(dec!("10")));
+        assert_eq!(time_weighted_average(&[(20, dec!("10"))], 20, 20), Some(dec!("10")));
+        assert_eq!(time_weighted_average(&[], 0, 20), None);
+    }
+
+    #[test]
+    fn test_draw_index() {
+        let mut seed = [0u8; 32];
+        seed[0] = 7;
+        assert_eq!(draw_index(Hash(seed), 5), 2);
+        assert_eq!(draw_index(Hash(seed), 1), 0);
+        assert_eq!(draw_index(Hash([0xff; 32]), 10), u64::MAX % 10);
+    }
+}
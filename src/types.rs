@@ -0,0 +1,1235 @@
+use scrypto::prelude::*;
+
+use crate::settlement::*;
+
+#[derive(ScryptoSbor, Clone)]
+pub enum ResourceSpecifier {
+    Fungible {
+        resource_address: ResourceAddress,
+        amount: Decimal
+    },
+    NonFungible {
+        resource_address: ResourceAddress,
+        non_fungible_local_ids: IndexSet<NonFungibleLocalId>
+    },
+    // Any `count` non-fungibles of a collection, whatever their local IDs
+    NonFungibleCount {
+        resource_address: ResourceAddress,
+        count: u64
+    }
+}
+
+impl ResourceSpecifier {
+
+    pub fn get_resource_address(&self) -> ResourceAddress {
+        match self {
+            Self::Fungible {
+                resource_address, ..
+            }
+            | Self::NonFungible {
+                resource_address, ..
+            }
+            | Self::NonFungibleCount {
+                resource_address, ..
+            } => *resource_address,
+        }
+    }
+
+    // Checks whether a bucket of the specified resource holds enough to fill it
+    pub fn is_satisfied_by(&self, bucket: &Bucket) -> bool {
+        match self {
+            Self::Fungible { amount, .. } => bucket.amount() >= *amount,
+            Self::NonFungible { non_fungible_local_ids, .. } => non_fungible_local_ids
+                .iter()
+                .all(|non_fungible_local_id| bucket.as_non_fungible().contains_non_fungible(non_fungible_local_id)),
+            Self::NonFungibleCount { count, .. } => bucket.amount() >= Decimal::from(*count),
+        }
+    }
+}
+
+// Optional settings of an escrow, chosen at instantiation
+#[derive(ScryptoSbor, Clone, Default)]
+pub struct EscrowConfig {
+    // Local ID scheme of the EscrowBadge of a standalone escrow, escrows of a book share its integer badges
+    pub badge_id_kind: BadgeIdKind,
+    pub expiry_epoch: Option<u64>,
+    // Wall-clock deadline, the escrow expires at whichever of it and the expiry epoch comes first
+    pub expiry_time: Option<ClockDeadline>,
+    pub allow_partial_fills: bool,
+    // Whether each fill of a partially fillable escrow has to take everything that is left, or abort
+    pub fill_or_kill: bool,
+    // Least a single fill of a partially fillable escrow has to pay, unless it takes everything that is left
+    pub min_fill_amount: Option<Decimal>,
+    // Most a single taker can pay of the fungible requested resource over all their fills
+    pub per_taker_cap: Option<Decimal>,
+    // Most the escrow collects of the fungible requested resource over all its fills, after which it is sold out
+    pub sale_cap: Option<Decimal>,
+    pub access_badge: Option<ResourceAddress>,
+    // Accounts and components allowed to fill the escrow, as global caller or by proving ownership of the account.
+    // The instantiator can add and remove takers later on.
+    pub taker_allowlist: Option<IndexSet<ComponentAddress>>,
+    // Validator whose liquid stake units the single fungible requested resource has to be, checked at every fill
+    pub lsu_validator: Option<ComponentAddress>,
+    // Maturity and amount the unstake claim NFTs of a validator have to meet to pay for the escrow
+    pub unstake_claims: Option<UnstakeClaimPolicy>,
+    // Minimum underlying value the pool units paid for the escrow have to redeem for
+    pub pool_units: Option<PoolUnitPolicy>,
+    // Credential, e.g. an identity NFT, takers have to present a proof of to `exchange_with_credential`
+    pub compliance_credential: Option<ResourceAddress>,
+    pub dutch_auction: Option<DutchAuction>,
+    pub oracle_pricing: Option<OraclePricing>,
+    pub hash_lock: Option<HashLock>,
+    // Blake2b hash of the invite code takers have to supply to `exchange_with_secret`
+    pub invite_code_hash: Option<Hash>,
+    // Off-ledger deal terms takers have to accept for their fill to settle
+    pub terms: Option<DealTerms>,
+    // Badge of the third party that settles disputes
+    pub arbiter_badge: Option<ResourceAddress>,
+    // Number of distinct arbiter badges that have to agree on a verdict, a single arbiter decides when 0 or 1
+    pub arbiter_threshold: u8,
+    // Epochs after the fill during which the payment is on hold and the fill can be disputed
+    pub dispute_window_epochs: Option<u64>,
+    // Collateral both parties of an arbitrated escrow can post, part of which the losing side of a dispute forfeits
+    pub collateral_bond: Option<CollateralBond>,
+    // Cover a third-party insurer can underwrite for one party of an arbitrated escrow, against a premium
+    pub insurance: Option<InsuranceTerms>,
+    // Epochs over which the collected payment unlocks to the instantiator, starting with the first fill
+    pub vesting_epochs: Option<u64>,
+    // Whether the requested amount is the price of one offered unit rather than of the whole offer
+    pub per_unit_pricing: bool,
+    // Curve the unit price of a per-unit escrow climbs along as the offer sells, from the requested amount
+    pub bonding_curve: Option<BondingCurve>,
+    // Prices of the whole offer in further accepted currencies, next to the single fungible requested resource
+    pub currency_prices: IndexMap<ResourceAddress, Decimal>,
+    // DEX that payments in other tokens are swapped through into the single fungible requested resource
+    pub dex_conversion: Option<DexConversion>,
+    // Share of the proceeds, after the protocol fee, that goes to the referrer of a fill
+    pub referral_fee_bps: u16,
+    // Shared FeeConfig component the protocol fee of every fill is read from, set by the factory
+    pub fee_config: Option<ComponentAddress>,
+    // Highest protocol fee the instantiator accepts from the fee config, whatever rate it is later set to
+    pub max_fee_bps: u16,
+    // Where the royalty of the creator of the single offered NFT resource is looked up, paid out of every fill
+    pub creator_royalty: Option<CreatorRoyaltyPolicy>,
+    // Share of its fungible deposit a side forfeits to the engaged counterparty when backing out of a
+    // committed deal, i.e. after accepting a counter-offer or once both legs of a two-sided escrow are in
+    pub cancellation_penalty_bps: u16,
+    // Whether the taker deposits their leg first, with both legs swapped by a separate `settle` call
+    pub two_sided: bool,
+    // Epochs the instantiator has to approve a taker's held payment before it is refunded to them
+    pub maker_approval_epochs: Option<u64>,
+    // Whether a payment may exceed the requested resources, with the surplus returned as change
+    pub matching_mode: MatchingMode,
+    // Authority that can recall a lost EscrowBadge of a standalone escrow
+    pub badge_recovery: Option<BadgeRecovery>,
+    // Account the EscrowBadge of a standalone escrow is bound to, so control of the escrow cannot be traded away
+    pub soulbound_badge: Option<ComponentAddress>,
+    // Account that receives the offered resources when the escrow is left expired for too long
+    pub beneficiary: Option<Beneficiary>,
+    // Where keepers return the offered resources of an expired escrow to, and what they earn for it
+    pub expiry_reclaim: Option<ExpiryReclaim>,
+    // Whether takers get a receipt NFT recording each exchange, and whether they can pass it on
+    pub taker_receipts: Option<TakerReceipts>,
+    // Whether both parties get a soulbound attestation once the escrow has been settled
+    pub reputation_attestations: bool,
+    // Whether both parties get a claim NFT for what they are owed, instead of the resources themselves,
+    // so delivery can happen in a later transaction or through a locker
+    pub claim_settlement: bool,
+    // Account locker the proceeds of every fill are pushed to, instead of waiting for `withdraw_resource`
+    pub proceeds_locker: Option<ProceedsLocker>,
+    // Account of the instantiator the proceeds of every fill are deposited to, kept in the escrow if it refuses them
+    pub maker_account: Option<ComponentAddress>,
+    // Shares in basis points of the fungible proceeds that go to further recipients as they are withdrawn,
+    // the instantiator keeps the rest
+    pub payout_split: IndexMap<PayoutRecipient, u16>,
+    // Terms that settle the escrow on their own instead of the requested resources, e.g. 95 xUSDT instead of 100 xUSDC
+    pub alternative_resources: Vec<ResourceSpecifier>,
+}
+
+// Authority that can recall the EscrowBadge of a standalone escrow for an instantiator who lost it.
+// A recovery is announced first, and the instantiator can still stop it during the delay.
+#[derive(ScryptoSbor, Clone)]
+pub struct BadgeRecovery {
+    pub authority: ResourceAddress,
+    pub delay_epochs: u64,
+}
+
+// Bond each party of an arbitrated escrow can post next to the deal. An award to one side slashes
+// the other side's bond, paying the arbiter their cut of it and the rest to the winner.
+#[derive(ScryptoSbor, Clone)]
+pub struct CollateralBond {
+    pub resource_address: ResourceAddress,
+    pub amount: Decimal,
+    pub slash_bps: u16,
+    // Share of the slashed bond paid to the arbiter
+    pub arbiter_bps: u16,
+}
+
+// Cover of one party of an arbitrated escrow. The insured pays the premium up front, which the insurer
+// takes when depositing the coverage. A verdict against the insured within the coverage window pays it out.
+#[derive(ScryptoSbor, Clone)]
+pub struct InsuranceTerms {
+    pub insurer_badge: ResourceAddress,
+    pub insured: SettlementParty,
+    pub premium_resource: ResourceAddress,
+    pub premium_amount: Decimal,
+    pub coverage_resource: ResourceAddress,
+    pub coverage_amount: Decimal,
+    // Epochs from the underwriting during which a verdict against the insured triggers the coverage
+    pub coverage_epochs: u64,
+}
+
+// Accepts the unstake claim NFTs of a validator as payment, requested as a count of its claim NFT resource.
+// The claims of a fill have to be claimable by the maximum claim epoch and add up to the minimum claim amount.
+#[derive(ScryptoSbor, Clone)]
+pub struct UnstakeClaimPolicy {
+    pub validator: ComponentAddress,
+    pub max_claim_epoch: u64,
+    pub min_claim_amount: Decimal,
+}
+
+impl UnstakeClaimPolicy {
+
+    pub fn assert_accepts(&self, claims: &Bucket) {
+        let mut claim_amount = Decimal::ZERO;
+        for claim in claims.as_non_fungible().non_fungibles::<UnstakeClaim>() {
+            let claim = claim.data();
+            assert!(claim.claim_epoch.number() <= self.max_claim_epoch, "Unstake claim matures too late");
+            claim_amount += claim.claim_amount;
+        }
+        assert!(claim_amount >= self.min_claim_amount, "Unstake claims are worth less than the minimum claim amount");
+    }
+}
+
+// Accepts the pool units of a native pool as payment, requested as a fungible amount of its pool unit resource.
+// At fill time every paid pool unit has to redeem for at least the minimum amount of the underlying resource.
+#[derive(ScryptoSbor, Clone)]
+pub struct PoolUnitPolicy {
+    pub pool: ComponentAddress,
+    pub underlying: ResourceAddress,
+    pub min_value_per_unit: Decimal,
+}
+
+// DEX component a payment in another token is routed through, by calling its swap method with the payment bucket.
+// Every accepted input token sets the least amount of the requested token one unit of it has to swap for,
+// so a swap that slips below it fails the fill and leaves the taker's payment untouched.
+#[derive(ScryptoSbor, Clone)]
+pub struct DexConversion {
+    pub dex: ComponentAddress,
+    pub swap_method: String,
+    pub swap_output: SwapOutput,
+    pub min_rates: IndexMap<ResourceAddress, Decimal>,
+}
+
+// What the swap method of a DEX returns
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwapOutput {
+    // `fn swap(input: Bucket) -> Bucket`
+    Bucket,
+    // `fn swap(input: Bucket) -> (Bucket, Bucket)`, the output followed by whatever of the input was not swapped
+    BucketAndRemainder,
+}
+
+// Data of the unstake claim NFTs validators mint, mirroring the native `UnstakeData`
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct UnstakeClaim {
+    pub name: String,
+    pub claim_epoch: Epoch,
+    pub claim_amount: Decimal,
+}
+
+// How the local IDs of EscrowBadges are chosen
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug, Default)]
+pub enum BadgeIdKind {
+    // Numbered from 1 in the order the component mints them
+    #[default]
+    Integer,
+    Ruid,
+    // Label chosen by the instantiator, e.g. a deal reference
+    Label(String),
+}
+
+// Deadline as a UTC instant, against which the current time is compared rounded to the given precision
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClockDeadline {
+    pub instant: Instant,
+    pub precision: TimePrecision,
+}
+
+// New deadline the instantiator moves an escrow's expiry epoch or expiry time out to
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Deadline {
+    Epoch(u64),
+    Time(Instant),
+}
+
+impl ClockDeadline {
+
+    pub fn has_passed(&self) -> bool {
+        Clock::current_time_is_at_or_after(self.instant, self.precision)
+    }
+}
+
+// Fallback for an instantiator who can no longer reclaim an expired escrow, e.g. a dead man's switch.
+// Once the escrow has been expired for the grace period, anyone can pay its offered resources out.
+#[derive(ScryptoSbor, Clone)]
+pub struct Beneficiary {
+    pub account: ComponentAddress,
+    pub grace_epochs: u64,
+}
+
+// Identity the fills of a taker are tallied under, for escrows with a per-taker cap
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TakerId {
+    Credential(NonFungibleGlobalId),
+    Account(ComponentAddress),
+}
+
+// Honors the royalty the creator of an offered NFT resource asks for, up to a cap, so the creator cannot
+// raise it to take over the instantiator's proceeds.
+#[derive(ScryptoSbor, Clone)]
+pub struct CreatorRoyaltyPolicy {
+    pub source: CreatorRoyaltySource,
+    pub max_royalty_bps: u16,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum CreatorRoyaltySource {
+    // The "creator_royalty_bps" and "creator_royalty_badge" metadata of the NFT resource
+    Metadata,
+    // Registry component answering `get_creator_royalty(resource_address) -> Option<CreatorRoyalty>`
+    Registry(ComponentAddress),
+}
+
+// Royalty the creator of an NFT resource is owed on sales, claimed with a proof of the creator badge resource
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct CreatorRoyalty {
+    pub creator_badge: ResourceAddress,
+    pub royalty_bps: u16,
+}
+
+// Recipient of a slice of the proceeds. Accounts are paid on withdrawal, the slice of a badge recipient
+// waits in the escrow until it claims with a proof of the badge resource.
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PayoutRecipient {
+    Account(ComponentAddress),
+    Badge(ResourceAddress),
+}
+
+// Account locker, e.g. the instantiator's own, that stores the proceeds of a fill for the instantiator's account.
+// The escrow component has to satisfy the storer role of the locker.
+#[derive(ScryptoSbor, Clone)]
+pub struct ProceedsLocker {
+    pub locker: ComponentAddress,
+    pub account: ComponentAddress,
+}
+
+// Lets anyone return the offered resources of an expired escrow to the instantiator, for a bounty
+// in basis points of every fungible offered resource. For the grace period after expiry the escrow is
+// left to the instantiator, who can still cancel it themselves without paying the bounty.
+#[derive(ScryptoSbor, Clone)]
+pub struct ExpiryReclaim {
+    pub destination: ComponentAddress,
+    pub bounty_bps: u16,
+    pub grace_epochs: u64,
+}
+
+// Binds settlement to terms agreed off-ledger. Takers either pass the hash of the terms to
+// `exchange_with_terms` or present a proof of the acceptance badge handed out once they signed them.
+#[derive(ScryptoSbor, Clone)]
+pub struct DealTerms {
+    pub hash: Hash,
+    pub acceptance_badge: Option<ResourceAddress>,
+}
+
+// Lock of a hash-time-locked escrow, which takers open by revealing the secret behind the hash
+#[derive(ScryptoSbor, Clone)]
+pub struct HashLock {
+    // Keccak-256 hash of the secret, so the same lock can be set up on EVM ledgers
+    pub hash: Hash,
+    // Account the offered resources are refunded to once the escrow expires
+    pub refund_account: ComponentAddress,
+}
+
+// Price of an escrow asking for a fixed value of the requested resource, e.g. 100 USD worth of it.
+// The oracle component has to expose `get_price(resource_address) -> Decimal`, the value of one unit
+// of a resource in its quote currency, and `get_last_update_epoch(resource_address) -> u64` when
+// a maximum price age is set.
+#[derive(ScryptoSbor, Clone)]
+pub struct OraclePricing {
+    pub oracle: ComponentAddress,
+    pub quote_value: Decimal,
+    // Epochs after its last update during which an oracle price can be filled at
+    pub max_price_age_epochs: Option<u64>,
+    // Band around a time-weighted average price the oracle price has to be within for a fill
+    pub twap_guard: Option<TwapGuard>,
+}
+
+impl OraclePricing {
+
+    // Converts the quote value into an amount of the resource at the oracle's current price
+    pub fn amount_of(&self, resource_address: ResourceAddress) -> Decimal {
+        // Stale prices would let takers fill at off-market rates
+        assert!(self.is_price_fresh(resource_address), "Oracle price is stale");
+
+        self.quote_value / self.price(resource_address)
+    }
+
+    pub fn price(&self, resource_address: ResourceAddress) -> Decimal {
+        let oracle: Global<AnyComponent> = self.oracle.into();
+        let price: Decimal = oracle.call("get_price", &(resource_address,));
+        assert!(price > Decimal::ZERO, "Oracle price must be positive");
+        price
+    }
+
+    // Time-weighted average price the oracle keeps itself, for guards with the oracle as source
+    pub fn oracle_twap(&self, resource_address: ResourceAddress) -> Decimal {
+        let oracle: Global<AnyComponent> = self.oracle.into();
+        oracle.call("get_twap", &(resource_address,))
+    }
+
+    pub fn is_price_fresh(&self, resource_address: ResourceAddress) -> bool {
+        match self.max_price_age_epochs {
+            Some(max_price_age_epochs) => {
+                let oracle: Global<AnyComponent> = self.oracle.into();
+                let last_update_epoch: u64 = oracle.call("get_last_update_epoch", &(resource_address,));
+                Runtime::current_epoch().number().saturating_sub(last_update_epoch) <= max_price_age_epochs
+            },
+            None => true,
+        }
+    }
+}
+
+// Protects an oracle-priced escrow from a price pushed around within a single block, by only filling at prices
+// within `max_deviation_bps` of a time-weighted average
+#[derive(ScryptoSbor, Clone)]
+pub struct TwapGuard {
+    pub max_deviation_bps: u16,
+    pub source: TwapSource,
+}
+
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug)]
+pub enum TwapSource {
+    // The oracle's own `get_twap(resource_address) -> Decimal`
+    Oracle,
+    // Prices the escrow records at every fill and `observe_price` call, averaged over the window
+    Observations { window_epochs: u64 },
+}
+
+// Price of a Dutch auction, decaying from the start amount to the floor over an epoch range
+#[derive(ScryptoSbor, Clone)]
+pub struct DutchAuction {
+    pub start_amount: Decimal,
+    pub floor_amount: Decimal,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+    // Epochs between two price drops, 1 makes the decay linear
+    pub step_epochs: u64,
+}
+
+impl DutchAuction {
+
+    pub fn amount_at(&self, epoch: u64) -> Decimal {
+        dutch_auction_amount(
+            self.start_amount,
+            self.floor_amount,
+            self.start_epoch,
+            self.end_epoch,
+            self.step_epochs,
+            epoch,
+        )
+    }
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum BondingCurve {
+    // Unit price grows by the slope with every offered unit sold
+    Linear { slope: Decimal },
+    // Unit price is multiplied by the growth factor every time another step amount has been sold
+    Exponential { growth_factor: Decimal, step_amount: Decimal },
+    // Unit price is fixed within each tranche, the first of which is priced at the requested amount.
+    // Whatever is offered beyond the last tranche sells at its price.
+    Tranches { tranches: Vec<PriceTranche> },
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PriceTranche {
+    pub amount: Decimal,
+    pub price: Decimal,
+}
+
+impl BondingCurve {
+
+    // Unit price once `sold` of the offer has been sold
+    pub fn price_at(&self, start_price: Decimal, sold: Decimal) -> Decimal {
+        match self {
+            Self::Linear { slope } => start_price + *slope * sold,
+            Self::Exponential { growth_factor, step_amount } => {
+                stepped_curve_price(start_price, *growth_factor, *step_amount, sold)
+            },
+            Self::Tranches { .. } => tranche_at(&self.tranche_terms(), sold).1,
+        }
+    }
+
+    // Price of buying an amount of the offer, once `sold` of it has been sold
+    pub fn cost(&self, start_price: Decimal, sold: Decimal, amount: Decimal) -> Decimal {
+        match self {
+            Self::Linear { slope } => linear_curve_cost(start_price, *slope, sold, amount),
+            Self::Exponential { growth_factor, step_amount } => {
+                stepped_curve_cost(start_price, *growth_factor, *step_amount, sold, amount)
+            },
+            Self::Tranches { .. } => tranche_cost(&self.tranche_terms(), sold, amount),
+        }
+    }
+
+    // Amount of the offer a payment buys, once `sold` of it has been sold
+    pub fn amount_for(&self, start_price: Decimal, sold: Decimal, payment: Decimal) -> Decimal {
+        match self {
+            Self::Linear { slope } => linear_curve_amount(start_price, *slope, sold, payment),
+            Self::Exponential { growth_factor, step_amount } => {
+                stepped_curve_amount(start_price, *growth_factor, *step_amount, sold, payment)
+            },
+            Self::Tranches { .. } => tranche_amount(&self.tranche_terms(), sold, payment),
+        }
+    }
+
+    // Index of the tranche selling once `sold` of the offer has been sold, for curves made of tranches
+    pub fn tranche_index(&self, sold: Decimal) -> Option<u32> {
+        match self {
+            Self::Tranches { .. } => Some(tranche_at(&self.tranche_terms(), sold).0 as u32),
+            _ => None,
+        }
+    }
+
+    fn tranche_terms(&self) -> Vec<(Decimal, Decimal)> {
+        match self {
+            Self::Tranches { tranches } => tranches.iter().map(|tranche| (tranche.amount, tranche.price)).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct EscrowDetails {
+    pub requested_resources: Vec<ResourceSpecifier>,
+    pub offered_resources: IndexMap<ResourceAddress, Decimal>,
+    pub collected_resources: IndexMap<ResourceAddress, Decimal>,
+    pub filled_amounts: IndexMap<ResourceAddress, Decimal>,
+    pub escrow_nft: ResourceAddress,
+    pub config: EscrowConfig,
+    pub status: EscrowStatus,
+    pub chosen_alternative: Option<ResourceSpecifier>,
+    pub paused: bool,
+}
+
+// Quote of the payment for a given amount of an offered resource, with the protocol fees taken out of it
+#[derive(ScryptoSbor, Clone)]
+pub struct RequiredPayment {
+    pub payment: Vec<ResourceSpecifier>,
+    pub fees: IndexMap<ResourceAddress, Decimal>,
+}
+
+// Outcome of a dry-run fill. The failure is the message the fill would abort with, if any.
+#[derive(ScryptoSbor, Clone)]
+pub struct ExchangePreview {
+    pub received: IndexMap<ResourceAddress, Decimal>,
+    pub change: Decimal,
+    pub fees: IndexMap<ResourceAddress, Decimal>,
+    pub failure: Option<String>,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct EscrowListing {
+    pub escrow_component: ComponentAddress,
+    pub escrow_nft: ResourceAddress,
+    pub requested_resources: Vec<ResourceSpecifier>,
+    pub offered_resources: IndexMap<ResourceAddress, Decimal>,
+    pub created_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct CounterOffer {
+    pub requested_resources: Vec<ResourceSpecifier>,
+    pub proposed_epoch: u64,
+    pub status: CounterOfferStatus,
+    // Account the proposer commits with, which is owed the cancellation penalty once the offer is accepted
+    pub proposer: Option<ComponentAddress>,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CounterOfferStatus {
+    Pending,
+    Accepted,
+    Rejected
+}
+
+// Terms of an English auction, chosen at instantiation
+#[derive(ScryptoSbor, Clone)]
+pub struct EnglishAuctionTerms {
+    pub bid_resource: ResourceAddress,
+    pub min_bid: Decimal,
+    pub end_epoch: u64,
+    // Commitment to a hidden reserve price, revealed by the instantiator at settlement
+    pub reserve_hash: Option<Hash>,
+    // Least a bid has to add to the best one, so outbidding by dust cannot grief the other bidders
+    pub min_increment: Option<BidIncrement>,
+    pub anti_sniping: Option<AntiSniping>,
+    // Epochs the instantiator has to settle the ended auction, after which the best bidder can take their bid back
+    pub settlement_window_epochs: u64,
+}
+
+// Pushes the end of an auction back whenever a bid lands in its final epochs, so the other bidders
+// get to answer it, up to a maximum extension in total
+#[derive(ScryptoSbor, Clone, Copy, Debug)]
+pub struct AntiSniping {
+    pub window_epochs: u64,
+    pub extension_epochs: u64,
+    pub max_extension_epochs: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, Debug)]
+pub enum BidIncrement {
+    Absolute { amount: Decimal },
+    // Share of the best bid in basis points
+    Relative { bps: u16 },
+}
+
+// Terms of a sealed-bid auction, chosen at instantiation
+#[derive(ScryptoSbor, Clone)]
+pub struct SealedBidTerms {
+    pub bid_resource: ResourceAddress,
+    pub min_bid: Decimal,
+    // Bond every commitment has to lock up, forfeited when the bid is not revealed
+    pub bond_amount: Decimal,
+    pub commit_end_epoch: u64,
+    pub reveal_end_epoch: u64,
+    // Commitment to a hidden reserve price, revealed by the instantiator at settlement
+    pub reserve_hash: Option<Hash>,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct Milestone {
+    pub amount: Decimal,
+    pub status: MilestoneStatus,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MilestoneStatus {
+    Pending,
+    Released,
+    Refunded
+}
+
+// Deposit of one contributor to a crowdfund, entitling its holder to a pro-rata share of the offer
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ContributionBadge {
+    pub(crate) crowdfund_component: ComponentAddress,
+    pub(crate) amount: Decimal,
+    pub(crate) contributed_epoch: u64
+}
+
+// Right of the payee of a streaming escrow to claim the offered resource as it streams
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct StreamBadge {
+    pub(crate) stream_component: ComponentAddress,
+    pub(crate) amount: Decimal,
+    pub(crate) start_epoch: u64,
+    pub(crate) end_epoch: u64
+}
+
+// Terms of a raffle, chosen at instantiation
+#[derive(ScryptoSbor, Clone)]
+pub struct RaffleTerms {
+    pub ticket_resource: ResourceAddress,
+    pub ticket_price: Decimal,
+    pub deadline_epoch: u64,
+    // Whether losing tickets are refunded after the draw, the instantiator keeps every ticket payment otherwise
+    pub refund_losers: bool,
+    // Component exposing `get_random_seed() -> Hash`, which seeds the draw. Ledger data would let whoever
+    // submits the draw grind for a winner.
+    pub randomness: ComponentAddress,
+}
+
+// Entry of a raffle, which either wins the offered resources or gets refunded after the draw
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct RaffleTicket {
+    pub(crate) raffle_component: ComponentAddress,
+    pub(crate) purchased_epoch: u64
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Party {
+    Payer,
+    Payee,
+    Arbiter
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct PartyBadge {
+    pub(crate) party: Party,
+    pub(crate) escrow_component: ComponentAddress
+}
+
+// A bid of a sealed-bid auction, holding the bond from commitment and the bid from reveal
+#[derive(ScryptoSbor)]
+pub struct SealedBid {
+    pub(crate) bidder: ComponentAddress,
+    pub(crate) commitment: Hash,
+    pub(crate) bond: Vault,
+    pub(crate) bid: Vault,
+    pub(crate) revealed: bool,
+}
+
+// Transferability of the receipts minted to takers
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TakerReceipts {
+    Transferable,
+    // Receipts stay in the account of the taker
+    Soulbound,
+}
+
+// How strictly a payment has to match the requested resources
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MatchingMode {
+    // At least the requested resources, the rest is returned as change
+    #[default]
+    Minimum,
+    // Precisely the requested resources, nothing more. Partial fills may still pay less than what is left.
+    Exact,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EscrowStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Withdrawn,
+    Expired,
+    Disputed,
+    Refunded,
+    // Reached its sale cap with offered resources left, which go back to the instantiator with the proceeds
+    SoldOut,
+    // Wound up for good by the instantiator, with its badge burnt
+    Closed
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisputeAward {
+    Maker,
+    Taker,
+    // Percentage of the collected payment awarded to the taker, the rest goes to the instantiator
+    Split { taker_percentage: Decimal }
+}
+
+impl DisputeAward {
+
+    pub fn taker_share(&self) -> Decimal {
+        match self {
+            Self::Maker => Decimal::ZERO,
+            Self::Taker => Decimal::ONE,
+            Self::Split { taker_percentage } => {
+                assert!(
+                    *taker_percentage >= Decimal::ZERO && *taker_percentage <= Decimal::from(100),
+                    "Percentage must be between 0 and 100"
+                );
+                *taker_percentage / Decimal::from(100)
+            },
+        }
+    }
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct EscrowBadge {
+    #[mutable]
+    pub(crate) requested_resources: Vec<ResourceSpecifier>,
+    pub(crate) offered_resources: IndexMap<ResourceAddress, Decimal>,
+    pub(crate) created_epoch: u64,
+    #[mutable]
+    pub(crate) expiry_epoch: Option<u64>,
+    pub(crate) currency_prices: IndexMap<ResourceAddress, Decimal>,
+    pub(crate) escrow_component: ComponentAddress
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct TakerBadge {
+    #[mutable]
+    pub(crate) escrow_nft_id: NonFungibleLocalId,
+    pub(crate) escrow_component: ComponentAddress,
+    pub(crate) filled_epoch: u64
+}
+
+// Proof of purchase minted to the taker on every exchange
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct TakerReceipt {
+    pub(crate) escrow_nft_id: NonFungibleLocalId,
+    pub(crate) escrow_component: ComponentAddress,
+    pub(crate) paid: IndexMap<ResourceAddress, Decimal>,
+    pub(crate) received: IndexMap<ResourceAddress, Decimal>,
+    // Amount paid per unit received, when a single resource was paid for a single resource
+    pub(crate) price: Option<Decimal>,
+    pub(crate) epoch: u64
+}
+
+// Party of a settled escrow an attestation was minted to
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettlementParty {
+    Maker,
+    Taker,
+}
+
+// Track record of a party, minted to the taker on the final fill and to the instantiator on the final withdrawal
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ReputationAttestation {
+    pub(crate) escrow_nft_id: NonFungibleLocalId,
+    pub(crate) escrow_component: ComponentAddress,
+    pub(crate) party: SettlementParty,
+    pub(crate) outcome: EscrowStatus,
+    pub(crate) requested_resources: Vec<ResourceSpecifier>,
+    pub(crate) epoch: u64
+}
+
+// What one party of an escrow is owed, redeemable by whoever holds the claim
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct SettlementClaim {
+    pub(crate) escrow_nft_id: NonFungibleLocalId,
+    pub(crate) escrow_component: ComponentAddress,
+    pub(crate) party: SettlementParty,
+    pub(crate) amounts: IndexMap<ResourceAddress, Decimal>,
+    pub(crate) created_epoch: u64
+}
+
+// Events //
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowInstantiated {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub requested_resources: Vec<ResourceSpecifier>,
+    pub offered_resources: IndexMap<ResourceAddress, Decimal>,
+    pub expiry_epoch: Option<u64>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowFilled {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub paid: IndexMap<ResourceAddress, Decimal>,
+    pub released: IndexMap<ResourceAddress, Decimal>,
+    pub fees: IndexMap<ResourceAddress, Decimal>,
+    pub terms_hash: Option<Hash>,
+    pub credential_id: Option<NonFungibleLocalId>,
+}
+
+// A referrer is identified by a resource of its own, e.g. the badge of a frontend, and claims with a proof of it
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ReferralFeeAccrued {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub referrer: ResourceAddress,
+    pub fees: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CreatorRoyaltyAccrued {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub creator_badge: ResourceAddress,
+    pub royalties: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SettlementClaimed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub claim_nft_id: NonFungibleLocalId,
+    pub claimed: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct TakerLegDeposited {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub taker_account: ComponentAddress,
+    pub deposited: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ApprovalRequested {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub taker_nft_id: NonFungibleLocalId,
+    pub deadline_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FillApproved {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub taker_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct UnapprovedRefunded {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub taker_nft_id: NonFungibleLocalId,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct TakerLegWithdrawn {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+    pub penalty: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CancellationPenaltyPaid {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub counterparty: ComponentAddress,
+    pub penalty: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowCancelled {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowWithdrawn {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub withdrawn: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CounterOfferProposed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub counter_offer_id: u64,
+    pub requested_resources: Vec<ResourceSpecifier>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CounterOfferAccepted {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub counter_offer_id: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CounterOfferRejected {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub counter_offer_id: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionBidPlaced {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub bidder: ComponentAddress,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionSettled {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub winner: Option<ComponentAddress>,
+    pub winning_bid: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionBidReclaimed {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub bidder: ComponentAddress,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SealedBidCommitted {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub sealed_bid_id: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SealedBidRevealed {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub sealed_bid_id: u64,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionExtended {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ReserveNotMet {
+    pub auction_nft_id: NonFungibleLocalId,
+    pub reserve: Decimal,
+    pub best_bid: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CrowdfundContributed {
+    pub crowdfund_nft_id: NonFungibleLocalId,
+    pub contribution_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CrowdfundTargetReached {
+    pub crowdfund_nft_id: NonFungibleLocalId,
+    pub target_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CrowdfundShareClaimed {
+    pub crowdfund_nft_id: NonFungibleLocalId,
+    pub contribution_nft_id: NonFungibleLocalId,
+    pub claimed: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CrowdfundContributionRefunded {
+    pub crowdfund_nft_id: NonFungibleLocalId,
+    pub contribution_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CrowdfundOfferRecovered {
+    pub crowdfund_nft_id: NonFungibleLocalId,
+    pub recovered: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct StreamStarted {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub stream_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct StreamClaimed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub stream_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RaffleTicketBought {
+    pub raffle_nft_id: NonFungibleLocalId,
+    pub ticket_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RaffleDrawn {
+    pub raffle_nft_id: NonFungibleLocalId,
+    pub winning_ticket_id: NonFungibleLocalId,
+    pub ticket_count: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RaffleTicketRefunded {
+    pub raffle_nft_id: NonFungibleLocalId,
+    pub ticket_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct MilestoneReleased {
+    pub escrow_component: ComponentAddress,
+    pub milestone_index: u32,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct MilestonesAbandoned {
+    pub escrow_component: ComponentAddress,
+    pub refunded: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DisputeRaised {
+    pub escrow_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DisputeVoteCast {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub arbiter_nft_id: NonFungibleLocalId,
+    pub award: DisputeAward,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DisputeResolved {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub award: DisputeAward,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BondPosted {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub party: SettlementParty,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BondSlashed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub party: SettlementParty,
+    pub slashed: Decimal,
+    pub arbiter_cut: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PremiumPaid {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CoverageUnderwritten {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub coverage: Decimal,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CoveragePaidOut {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub party: SettlementParty,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BondWithdrawn {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub party: SettlementParty,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SecretRevealed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub secret: Vec<u8>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct UnsoldWithdrawn {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub retired_amount: Decimal,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OfferedAdded {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub resource_address: ResourceAddress,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RequestedUpdated {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub requested_resources: Vec<ResourceSpecifier>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AlternativeChosen {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub requested_resource: ResourceSpecifier,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PaymentConverted {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub input_resource: ResourceAddress,
+    pub input_amount: Decimal,
+    pub output_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct TakerAllowlistUpdated {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub taker: ComponentAddress,
+    pub allowed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DeadlineExtended {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub deadline: Deadline,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowClosed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+    pub withdrawn: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowFractionalized {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub maker_shares: ResourceAddress,
+    pub supply: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProceedsPaidOut {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub recipient: PayoutRecipient,
+    pub payout: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct MakerSharesClaimed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub shares: Decimal,
+    pub claimed: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowSoldOut {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub collected: Decimal,
+    pub unsold: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowPaused {
+    pub escrow_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowUnpaused {
+    pub escrow_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ExpiredReclaimed {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub returned: IndexMap<ResourceAddress, Decimal>,
+    pub bounty: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BeneficiaryPaid {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub beneficiary: ComponentAddress,
+    pub paid: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowOwnershipTransferred {
+    pub previous_escrow_nft_id: NonFungibleLocalId,
+    pub escrow_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadgeRecoveryRequested {
+    pub escrow_nft_id: NonFungibleLocalId,
+    pub recoverable_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadgeRecoveryCancelled {
+    pub escrow_nft_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadgeRecovered {
+    pub escrow_nft_id: NonFungibleLocalId,
+}
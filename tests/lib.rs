@@ -1,68 +1,677 @@
 use scrypto_test::prelude::*;
 
-use scrypto101::hello_test::*;
+use scrypto101::escrow_test::*;
+use scrypto101::escrow_book_test::*;
+use scrypto101::escrow_router_test::*;
+use scrypto101::escrow_factory_test::*;
+use scrypto101::fee_config_test::*;
+use scrypto101::{
+    CreatorRoyaltyPolicy, CreatorRoyaltySource, Deadline, EscrowConfig, EscrowStatus, PayoutRecipient, ResourceSpecifier,
+};
 
 #[test]
-fn test_hello() {
-    // Setup the environment
-    let mut ledger = LedgerSimulatorBuilder::new().build();
-
-    // Create an account
-    let (public_key, _private_key, account) = ledger.new_allocated_account();
-
-    // Publish package
-    let package_address = ledger.compile_and_publish(this_package!());
-
-    // Test the `instantiate_hello` function.
-    let manifest = ManifestBuilder::new()
-        .lock_fee_from_faucet()
-        .call_function(
-            package_address,
-            "Hello",
-            "instantiate_hello",
-            manifest_args!(),
-        )
-        .build();
-    let receipt = ledger.execute_manifest(
-        manifest,
-        vec![NonFungibleGlobalId::from_public_key(&public_key)],
-    );
-    println!("{:?}\n", receipt);
-    let component = receipt.expect_commit(true).new_component_addresses()[0];
-
-    // Test the `free_token` method.
-    let manifest = ManifestBuilder::new()
-        .lock_fee_from_faucet()
-        .call_method(component, "free_token", manifest_args!())
-        .call_method(
-            account,
-            "deposit_batch",
-            manifest_args!(ManifestExpression::EntireWorktop),
-        )
-        .build();
-    let receipt = ledger.execute_manifest(
-        manifest,
-        vec![NonFungibleGlobalId::from_public_key(&public_key)],
-    );
-    println!("{:?}\n", receipt);
-    receipt.expect_commit_success();
+fn test_exchange_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(60, &mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: payment.resource_address(&mut env)?,
+        amount: dec!("50"),
+    };
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![requested_resource],
+        vec![offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    let (offered, change) = escrow.exchange(vec![payment], &mut env)?;
+    let escrow_nft_proof = escrow_nft.0.create_proof_of_all(&mut env)?;
+    LocalAuthZone::push(escrow_nft_proof, &mut env)?;
+    let collected = escrow.withdraw_resource(&mut env)?;
+
+    // Assert
+    assert_eq!(offered[0].amount(&mut env)?, dec!("100"));
+    assert_eq!(change[0].amount(&mut env)?, dec!("10"));
+    assert_eq!(collected[0].amount(&mut env)?, dec!("50"));
+    assert_eq!(escrow.get_status(&mut env)?, EscrowStatus::Withdrawn);
+
+    Ok(())
+}
+
+#[test]
+fn test_cancel_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: XRD,
+        amount: dec!("50"),
+    };
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![requested_resource],
+        vec![offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    let escrow_nft_proof = escrow_nft.0.create_proof_of_all(&mut env)?;
+    LocalAuthZone::push(escrow_nft_proof, &mut env)?;
+    let returned = escrow.cancel_escrow(&mut env)?;
+
+    // Assert
+    assert_eq!(returned[0].amount(&mut env)?, dec!("100"));
+    assert!(escrow.is_closed(&mut env)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_escrow_book_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let (mut book, _fee_admin_badge) = EscrowBook::instantiate_escrow_book(0, package_address, &mut env)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(50, &mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: payment.resource_address(&mut env)?,
+        amount: dec!("50"),
+    };
+
+    let escrow_nft = book.create_escrow(vec![requested_resource], vec![offered], EscrowConfig::default(), &mut env)?;
+    let escrow_nft_id = escrow_nft.0.non_fungible_local_ids(&mut env)?.first().unwrap().clone();
+
+    // Act
+    let (offered, _change) = book.exchange(escrow_nft_id.clone(), vec![payment], &mut env)?;
+
+    // Assert
+    assert_eq!(offered[0].amount(&mut env)?, dec!("100"));
+    assert_eq!(book.get_status(escrow_nft_id, &mut env)?, EscrowStatus::Filled);
+
+    Ok(())
+}
+
+#[test]
+fn test_escrow_book_fee_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let (mut book, fee_admin_badge) = EscrowBook::instantiate_escrow_book(100, package_address, &mut env)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(50, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: payment_address,
+        amount: dec!("50"),
+    };
+
+    let escrow_nft = book.create_escrow(vec![requested_resource], vec![offered], EscrowConfig::default(), &mut env)?;
+    let escrow_nft_id = escrow_nft.0.non_fungible_local_ids(&mut env)?.first().unwrap().clone();
+
+    // Act
+    book.exchange(escrow_nft_id, vec![payment], &mut env)?;
+    let escrow_nft_proof = NonFungibleProof(escrow_nft.0.create_proof_of_all(&mut env)?);
+    let collected = book.withdraw_resource(escrow_nft_proof, &mut env)?;
+    let fee_admin_proof = fee_admin_badge.0.create_proof_of_all(&mut env)?;
+    let fees = book.withdraw_fees(fee_admin_proof, payment_address, &mut env)?;
+
+    // Assert
+    assert_eq!(collected[0].amount(&mut env)?, dec!("49.5"));
+    assert_eq!(fees.amount(&mut env)?, dec!("0.5"));
+
+    Ok(())
+}
+
+#[test]
+fn test_swap_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let first_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let second_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(60, &mut env)?;
+    let first_address = first_offered.resource_address(&mut env)?;
+    let second_address = second_offered.resource_address(&mut env)?;
+
+    let (mut first_escrow, _first_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: second_address, amount: dec!("50") }],
+        vec![first_offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+    let (second_escrow, _second_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: first_address, amount: dec!("100") }],
+        vec![second_offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    let surplus = first_escrow.swap_with(second_escrow.into(), &mut env)?;
+
+    // Assert
+    let mut surplus_of_second = Decimal::ZERO;
+    for bucket in surplus {
+        if bucket.resource_address(&mut env)? == second_address {
+            surplus_of_second += bucket.amount(&mut env)?;
+        }
+    }
+    assert_eq!(surplus_of_second, dec!("10"));
+    assert_eq!(first_escrow.get_status(&mut env)?, EscrowStatus::Filled);
+
+    Ok(())
+}
+
+#[test]
+fn test_escrow_book_batch_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let (mut book, _fee_admin_badge) = EscrowBook::instantiate_escrow_book(0, package_address, &mut env)?;
+
+    let first_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let second_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(200, &mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: XRD,
+        amount: dec!("50"),
+    };
+
+    // Act
+    let escrow_nfts = book.create_escrows(
+        vec![(requested_resource.clone(), first_offered), (requested_resource, second_offered)],
+        EscrowConfig::default(),
+        &mut env,
+    )?;
+
+    // Assert
+    let escrow_nft_ids = escrow_nfts.0.non_fungible_local_ids(&mut env)?;
+    assert_eq!(escrow_nft_ids.len(), 2);
+    for escrow_nft_id in escrow_nft_ids {
+        assert_eq!(book.get_status(escrow_nft_id, &mut env)?, EscrowStatus::Open);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_router_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let first_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let second_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(60, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(90, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+
+    let (first_escrow, _first_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("50") }],
+        vec![first_offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+    let (second_escrow, _second_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("30") }],
+        vec![second_offered],
+        EscrowConfig::default(),
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    let (purchases, change) = EscrowRouter::fill_escrows(
+        payment,
+        vec![first_escrow.into(), second_escrow.into()],
+        package_address,
+        &mut env,
+    )?;
+
+    // Assert
+    assert_eq!(purchases.len(), 2);
+    assert_eq!(purchases[0].amount(&mut env)?, dec!("100"));
+    assert_eq!(purchases[1].amount(&mut env)?, dec!("60"));
+    assert_eq!(change.amount(&mut env)?, dec!("10"));
+
+    Ok(())
+}
+
+#[test]
+fn test_factory_sync_status_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let mut factory = EscrowFactory::instantiate_factory(None, package_address, &mut env)?;
+
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(50, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+    let mut escrow_addresses = Vec::new();
+    for _ in 0..3 {
+        let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+            .mint_initial_supply(100, &mut env)?;
+        let (escrow, _escrow_nft) = factory.create_escrow(
+            vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("50") }],
+            vec![offered],
+            EscrowConfig::default(),
+            RoyaltyAmount::Free,
+            &mut env,
+        )?;
+        escrow_addresses.push(ComponentAddress::try_from(escrow.0).unwrap());
+    }
+    let mut first_escrow = Escrow(*escrow_addresses[0].as_node_id());
+    first_escrow.exchange(vec![payment], &mut env)?;
+
+    // Act
+    let delisted_open = factory.sync_status(escrow_addresses[1], &mut env)?;
+    let delisted_filled = factory.sync_status(escrow_addresses[0], &mut env)?;
+    let delisted_again = factory.sync_status(escrow_addresses[0], &mut env)?;
+
+    // Assert
+    assert!(!delisted_open);
+    assert!(delisted_filled);
+    assert!(!delisted_again);
+    assert_eq!(factory.get_open_escrow_count(&mut env)?, 2);
+    assert_eq!(factory.get_escrow_count(&mut env)?, 3);
+    // The last open escrow took the slot of the delisted one
+    let open_escrows = factory.list_open_escrows(0, 10, &mut env)?;
+    assert_eq!(open_escrows.len(), 2);
+    assert_eq!(open_escrows[0].escrow_component, escrow_addresses[2]);
+    assert_eq!(open_escrows[1].escrow_component, escrow_addresses[1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_factory_fee_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let fee_recipient = new_account(&mut env)?;
+    let (fee_config, _fee_admin_badge) = FeeConfig::instantiate_fee_config(100, fee_recipient, package_address, &mut env)?;
+    let fee_config_address = ComponentAddress::try_from(fee_config).unwrap();
+    let mut factory = EscrowFactory::instantiate_factory(Some(fee_config_address), package_address, &mut env)?;
+
+    let first_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let second_offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+    let second_payment = payment.take(dec!("50"), &mut env)?;
+    let requested_resource = ResourceSpecifier::Fungible {
+        resource_address: payment_address,
+        amount: dec!("50"),
+    };
+
+    // The first instantiator accepts the full fee, the second caps it below the rate of the fee config
+    let (first_escrow, first_nft) = factory.create_escrow(
+        vec![requested_resource.clone()],
+        vec![first_offered],
+        EscrowConfig { max_fee_bps: 500, ..Default::default() },
+        RoyaltyAmount::Free,
+        &mut env,
+    )?;
+    let (second_escrow, second_nft) = factory.create_escrow(
+        vec![requested_resource],
+        vec![second_offered],
+        EscrowConfig { max_fee_bps: 50, ..Default::default() },
+        RoyaltyAmount::Free,
+        &mut env,
+    )?;
+    let mut first_escrow = Escrow(first_escrow.0);
+    let mut second_escrow = Escrow(second_escrow.0);
+
+    // Act
+    first_escrow.exchange(vec![payment], &mut env)?;
+    second_escrow.exchange(vec![second_payment], &mut env)?;
+    LocalAuthZone::push(first_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+    LocalAuthZone::push(second_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+    let first_collected = first_escrow.withdraw_resource(&mut env)?;
+    let second_collected = second_escrow.withdraw_resource(&mut env)?;
+
+    // Assert
+    assert_eq!(first_collected[0].amount(&mut env)?, dec!("49.5"));
+    assert_eq!(second_collected[0].amount(&mut env)?, dec!("49.75"));
+    let fees = withdraw_from_account(fee_recipient, payment_address, dec!("0.75"), &mut env)?;
+    assert_eq!(fees.amount(&mut env)?, dec!("0.75"));
+
+    Ok(())
+}
+
+#[test]
+fn test_factory_fee_exemption_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let fee_recipient = new_account(&mut env)?;
+    let (mut fee_config, fee_admin_badge) =
+        FeeConfig::instantiate_fee_config(100, fee_recipient, package_address, &mut env)?;
+    let fee_config_address = ComponentAddress::try_from(fee_config).unwrap();
+    let mut factory = EscrowFactory::instantiate_factory(Some(fee_config_address), package_address, &mut env)?;
+
+    let exemption_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(1, &mut env)?;
+    let fee_admin_proof = fee_admin_badge.0.create_proof_of_all(&mut env)?;
+    fee_config.set_fee_exemption(fee_admin_proof, exemption_badge.resource_address(&mut env)?, true, &mut env)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(50, &mut env)?;
+    let (escrow, escrow_nft) = factory.create_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment.resource_address(&mut env)?, amount: dec!("50") }],
+        vec![offered],
+        EscrowConfig { max_fee_bps: 500, ..Default::default() },
+        RoyaltyAmount::Free,
+        &mut env,
+    )?;
+    let mut escrow = Escrow(escrow.0);
+
+    // Act
+    let exemption_proof = exemption_badge.create_proof_of_all(&mut env)?;
+    let (offered, _change) = escrow.exchange_fee_exempt(vec![payment], exemption_proof, &mut env)?;
+    LocalAuthZone::push(escrow_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+    let collected = escrow.withdraw_resource(&mut env)?;
+
+    // Assert
+    assert_eq!(offered[0].amount(&mut env)?, dec!("100"));
+    assert_eq!(collected[0].amount(&mut env)?, dec!("50"));
+
+    Ok(())
+}
+
+#[test]
+fn test_creator_royalty_cap_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let creator_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(1, &mut env)?;
+    let creator_badge_address = creator_badge.resource_address(&mut env)?;
+    // The creator asks for 20%, more than the instantiator accepts
+    let offered = ResourceBuilder::new_integer_non_fungible::<EmptyNonFungibleData>(OwnerRole::None)
+        .metadata(metadata! {
+            init {
+                "creator_royalty_bps" => 2_000u32, locked;
+                "creator_royalty_badge" => GlobalAddress::from(creator_badge_address), locked;
+            }
+        })
+        .mint_initial_supply([(IntegerNonFungibleLocalId::new(1), EmptyNonFungibleData {})], &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment.resource_address(&mut env)?, amount: dec!("100") }],
+        vec![offered],
+        EscrowConfig {
+            creator_royalty: Some(CreatorRoyaltyPolicy {
+                source: CreatorRoyaltySource::Metadata,
+                max_royalty_bps: 500,
+            }),
+            ..Default::default()
+        },
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    escrow.exchange(vec![payment], &mut env)?;
+    LocalAuthZone::push(escrow_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+    let collected = escrow.withdraw_resource(&mut env)?;
+    let creator_proof = creator_badge.create_proof_of_all(&mut env)?;
+    let royalties = escrow.claim_creator_royalties(creator_proof, &mut env)?;
+
+    // Assert
+    assert_eq!(collected[0].amount(&mut env)?, dec!("95"));
+    assert_eq!(royalties[0].amount(&mut env)?, dec!("5"));
+
+    Ok(())
+}
+
+#[test]
+fn test_payout_split_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let payout_account = new_account(&mut env)?;
+    let payout_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(1, &mut env)?;
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    // Whole units only, so both slices are rounded down
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .divisibility(DIVISIBILITY_NONE)
+        .mint_initial_supply(50, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+
+    let mut payout_split = IndexMap::new();
+    payout_split.insert(PayoutRecipient::Account(payout_account), 2_500);
+    payout_split.insert(PayoutRecipient::Badge(payout_badge.resource_address(&mut env)?), 3_333);
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("50") }],
+        vec![offered],
+        EscrowConfig { payout_split, ..Default::default() },
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+
+    // Act
+    escrow.exchange(vec![payment], &mut env)?;
+    LocalAuthZone::push(escrow_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+    let collected = escrow.withdraw_resource(&mut env)?;
+    let payout_proof = payout_badge.create_proof_of_all(&mut env)?;
+    let badge_payout = escrow.claim_payout(payout_proof, &mut env)?;
+
+    // Assert
+    // 12.5 and 16.665 are rounded down to 12 and 16, the instantiator keeps the remainder
+    let account_payout = withdraw_from_account(payout_account, payment_address, dec!("12"), &mut env)?;
+    assert_eq!(account_payout.amount(&mut env)?, dec!("12"));
+    assert_eq!(badge_payout[0].amount(&mut env)?, dec!("16"));
+    assert_eq!(collected[0].amount(&mut env)?, dec!("22"));
+
+    Ok(())
 }
 
 #[test]
-fn test_hello_with_test_environment() -> Result<(), RuntimeError> {
+fn test_fractionalize_with_test_environment() -> Result<(), RuntimeError> {
     // Arrange
     let mut env = TestEnvironment::new();
-    let package_address = 
+    let package_address =
         PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
 
-    let mut hello = Hello::instantiate_hello(package_address, &mut env)?;
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(50, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+    let expiry_epoch = env.get_current_epoch().number() + 10;
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("50") }],
+        vec![offered],
+        EscrowConfig { expiry_epoch: Some(expiry_epoch), ..Default::default() },
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+    let shares = escrow.fractionalize(escrow_nft, dec!("100"), &mut env)?;
 
     // Act
-    let bucket = hello.free_token(&mut env)?;
+    escrow.exchange(vec![payment], &mut env)?;
+    let first_shares = shares.0.take(dec!("25"), &mut env)?;
+    let first_claim = escrow.claim_share(scrypto::prelude::FungibleBucket(first_shares), &mut env)?;
+    let second_claim = escrow.claim_share(shares, &mut env)?;
 
     // Assert
-    let amount = bucket.amount(&mut env)?;
-    assert_eq!(amount, dec!("1"));
+    assert_eq!(amount_of(&first_claim, payment_address, &mut env)?, dec!("12.5"));
+    assert_eq!(amount_of(&second_claim, payment_address, &mut env)?, dec!("37.5"));
+    assert_eq!(escrow.get_status(&mut env)?, EscrowStatus::Closed);
 
     Ok(())
 }
+
+#[test]
+fn test_extend_deadline_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let current_epoch = env.get_current_epoch().number();
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: XRD, amount: dec!("50") }],
+        vec![offered],
+        EscrowConfig { expiry_epoch: Some(current_epoch + 10), ..Default::default() },
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+    LocalAuthZone::push(escrow_nft.0.create_proof_of_all(&mut env)?, &mut env)?;
+
+    // Act
+    escrow.extend_deadline(Deadline::Epoch(current_epoch + 20), &mut env)?;
+    env.set_current_epoch(Epoch::of(current_epoch + 20));
+    let extended_after_expiry = escrow.extend_deadline(Deadline::Epoch(current_epoch + 30), &mut env);
+
+    // Assert
+    assert_eq!(escrow.get_details(&mut env)?.config.expiry_epoch, Some(current_epoch + 20));
+    // An expired escrow stays expired
+    assert!(extended_after_expiry.is_err());
+    assert_eq!(escrow.get_status(&mut env)?, EscrowStatus::Expired);
+
+    Ok(())
+}
+
+#[test]
+fn test_close_with_test_environment() -> Result<(), RuntimeError> {
+    // Arrange
+    let mut env = TestEnvironment::new();
+    let package_address =
+        PackageFactory::compile_and_publish(this_package!(), &mut env, CompileProfile::Fast)?;
+
+    let offered = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(100, &mut env)?;
+    let offered_address = offered.resource_address(&mut env)?;
+    let payment = ResourceBuilder::new_fungible(OwnerRole::None)
+        .mint_initial_supply(25, &mut env)?;
+    let payment_address = payment.resource_address(&mut env)?;
+
+    let (mut escrow, escrow_nft) = Escrow::instantiate_escrow(
+        vec![ResourceSpecifier::Fungible { resource_address: payment_address, amount: dec!("50") }],
+        vec![offered],
+        EscrowConfig { allow_partial_fills: true, ..Default::default() },
+        RoyaltyAmount::Free,
+        package_address,
+        &mut env,
+    )?;
+    escrow.exchange(vec![payment], &mut env)?;
+
+    // Act
+    let returned = escrow.close(escrow_nft, &mut env)?;
+
+    // Assert
+    assert_eq!(amount_of(&returned, offered_address, &mut env)?, dec!("50"));
+    assert_eq!(amount_of(&returned, payment_address, &mut env)?, dec!("25"));
+    assert_eq!(escrow.get_status(&mut env)?, EscrowStatus::Closed);
+    assert!(escrow.is_closed(&mut env)?);
+
+    Ok(())
+}
+
+// Account anyone can withdraw from, standing in for a user's account
+fn new_account(env: &mut TestEnvironment<InMemorySubstateDatabase>) -> Result<ComponentAddress, RuntimeError> {
+    let account: AccountCreateAdvancedOutput = env.call_function_typed(
+        ACCOUNT_PACKAGE,
+        ACCOUNT_BLUEPRINT,
+        ACCOUNT_CREATE_ADVANCED_IDENT,
+        &AccountCreateAdvancedInput { owner_role: OwnerRole::Fixed(rule!(allow_all)), address_reservation: None },
+    )?;
+    Ok(account.0)
+}
+
+fn withdraw_from_account(
+    account: ComponentAddress,
+    resource_address: ResourceAddress,
+    amount: Decimal,
+    env: &mut TestEnvironment<InMemorySubstateDatabase>,
+) -> Result<Bucket, RuntimeError> {
+    env.call_method_typed(account, ACCOUNT_WITHDRAW_IDENT, &AccountWithdrawInput { resource_address, amount })
+}
+
+// Total of the given resource over a list of buckets
+fn amount_of(
+    buckets: &[Bucket],
+    resource_address: ResourceAddress,
+    env: &mut TestEnvironment<InMemorySubstateDatabase>,
+) -> Result<Decimal, RuntimeError> {
+    let mut amount = Decimal::ZERO;
+    for bucket in buckets {
+        if bucket.resource_address(env)? == resource_address {
+            amount += bucket.amount(env)?;
+        }
+    }
+    Ok(amount)
+}